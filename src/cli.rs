@@ -0,0 +1,266 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::fs::write;
+
+const USAGE: &str = "usage: dun-jun [spawn-report [--seed <n>] [--csv <file>]]
+       dun-jun preview [--seed <n>] [--architect <name>] [--depth <n>] [--size <w>x<h>] [--out <file>] [--png <file>]
+       dun-jun check-maps [--seeds <n>] [--size <w>x<h>]";
+const MAP_SAMPLES: usize = 100;
+const ARCHITECTS: [ArchitectKind; 6] = [
+    ArchitectKind::Rooms,
+    ArchitectKind::CellularAutomata,
+    ArchitectKind::DrunkardsWalk,
+    ArchitectKind::WaveFunctionCollapse,
+    ArchitectKind::Maze,
+    ArchitectKind::Empty,
+];
+
+pub enum Command {
+    SpawnReport {
+        seed: u64,
+        csv: Option<String>,
+    },
+    Preview {
+        seed: Option<u64>,
+        architect: Option<ArchitectKind>,
+        size: Option<(i32, i32)>,
+        depth: usize,
+        out: Option<String>,
+        png: Option<String>,
+    },
+    CheckMaps {
+        seeds: u64,
+        size: Option<(i32, i32)>,
+    },
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let command = match args.first() {
+            Some(command) => command.as_str(),
+            None => return Ok(None),
+        };
+        let options = options(&args[1..])?;
+        let allowed: &[&str] = match command {
+            "spawn-report" => &["--seed", "--csv"],
+            "preview" => &[
+                "--seed",
+                "--architect",
+                "--depth",
+                "--size",
+                "--out",
+                "--png",
+            ],
+            "check-maps" => &["--seeds", "--size"],
+            _ => return Err(USAGE.to_string()),
+        };
+        if let Some(unknown) = options.keys().find(|key| !allowed.contains(key)) {
+            return Err(format!("unknown option {}\n{}", unknown, USAGE));
+        }
+        let path = |key: &str| options.get(key).map(|path| path.to_string());
+        let size = options
+            .get("--size")
+            .map(|size| parse_size(size))
+            .transpose()?;
+        let seed = options
+            .get("--seed")
+            .map(|seed| seed.parse().map_err(|_| format!("invalid seed {}", seed)))
+            .transpose()?;
+
+        match command {
+            "spawn-report" => Ok(Some(Command::SpawnReport {
+                seed: seed.unwrap_or(0),
+                csv: path("--csv"),
+            })),
+            "check-maps" => Ok(Some(Command::CheckMaps {
+                seeds: options
+                    .get("--seeds")
+                    .map(|seeds| {
+                        seeds
+                            .parse()
+                            .map_err(|_| format!("invalid seeds {}", seeds))
+                    })
+                    .transpose()?
+                    .unwrap_or(200),
+                size,
+            })),
+            _ => Ok(Some(Command::Preview {
+                seed,
+                architect: options
+                    .get("--architect")
+                    .map(|name| {
+                        ron::de::from_str(name).map_err(|_| format!("unknown architect {}", name))
+                    })
+                    .transpose()?,
+                size,
+                depth: options
+                    .get("--depth")
+                    .map(|depth| {
+                        depth
+                            .parse()
+                            .map_err(|_| format!("invalid depth {}", depth))
+                    })
+                    .transpose()?
+                    .unwrap_or(0),
+                out: path("--out"),
+                png: path("--png"),
+            })),
+        }
+    }
+
+    pub fn run(&self, config: &GameConfig, templates: &Templates, themes: &Themes) -> BError {
+        match self {
+            Command::SpawnReport { seed, csv } => {
+                let report = templates.spawn_report(
+                    config,
+                    &average_spawn_points(config, templates, themes, *seed),
+                );
+                report.print_table();
+                if let Some(path) = csv {
+                    write(path, report.to_csv())?;
+                    println!("Wrote {}", path);
+                }
+            }
+            Command::Preview {
+                seed,
+                architect,
+                size,
+                depth,
+                out,
+                png,
+            } => {
+                let seed = seed.unwrap_or_else(|| RandomNumberGenerator::new().next_u64());
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let mut config = config.clone();
+                let level = config.depths.entry(*depth).or_default();
+                level.architect = architect.or(level.architect);
+                level.size = size.or(level.size);
+
+                let mut map_builder = crate::build_map(&mut rng, &config, themes, *depth);
+                crate::place_goal(
+                    &mut World::default(),
+                    templates,
+                    &config,
+                    &mut map_builder,
+                    &mut rng,
+                    *depth,
+                    false,
+                );
+
+                eprintln!("seed {}", seed);
+                [
+                    RoomKind::Standard,
+                    RoomKind::Lair,
+                    RoomKind::Treasure,
+                    RoomKind::Shrine,
+                    RoomKind::Empty,
+                ]
+                .iter()
+                .for_each(|kind| {
+                    let count = map_builder
+                        .rooms
+                        .iter()
+                        .filter(|room| room.kind == *kind)
+                        .count();
+                    eprintln!("{:?} rooms: {}", kind, count);
+                });
+                match out {
+                    Some(path) => write(path, map_builder.to_ascii())?,
+                    None if png.is_none() => print!("{}", map_builder.to_ascii()),
+                    None => {}
+                }
+                if let Some(path) = png {
+                    map_builder.to_png(path)?;
+                }
+            }
+            Command::CheckMaps { seeds, size } => {
+                let mut failures = 0;
+                let mut layouts: Vec<(String, usize, LevelConfig)> = Vec::new();
+                for depth in 0..config.levels {
+                    let mut level = config.level(depth);
+                    level.size = size.or(level.size);
+                    if level.steps.is_some() {
+                        layouts.push(("steps".to_string(), depth, level.clone()));
+                    }
+                    level.steps = None;
+                    for architect in ARCHITECTS.iter() {
+                        level.architect = Some(*architect);
+                        layouts.push((format!("{:?}", architect), depth, level.clone()));
+                    }
+                }
+                for seed in 0..*seeds {
+                    for (name, depth, level) in layouts.iter() {
+                        let mut rng = RandomNumberGenerator::seeded(seed);
+                        let mut config = config.clone();
+                        config.depths.insert(*depth, level.clone());
+                        let mut map_builder = crate::build_map(&mut rng, &config, themes, *depth);
+                        crate::place_goal(
+                            &mut World::default(),
+                            templates,
+                            &config,
+                            &mut map_builder,
+                            &mut rng,
+                            *depth,
+                            false,
+                        );
+                        map_builder.connectivity_errors().iter().for_each(|error| {
+                            failures += 1;
+                            println!("seed {} {} depth {}: {}", seed, name, depth, error);
+                        });
+                    }
+                }
+                if failures > 0 {
+                    return Err(format!("{} connectivity failure(s)", failures).into());
+                }
+                println!("{} seeds passed", seeds);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_size(size: &str) -> Result<(i32, i32), String> {
+    let invalid = || format!("invalid size {}, expected <w>x<h>", size);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: i32 = width.parse().map_err(|_| invalid())?;
+    let height: i32 = height.parse().map_err(|_| invalid())?;
+    if width < MIN_MAP_WIDTH || height < MIN_MAP_HEIGHT {
+        return Err(format!(
+            "size {} is smaller than {}x{}",
+            size, MIN_MAP_WIDTH, MIN_MAP_HEIGHT
+        ));
+    }
+    Ok((width, height))
+}
+
+fn options(args: &[String]) -> Result<HashMap<&str, &str>, String> {
+    args.chunks(2)
+        .map(|pair| match pair {
+            [key, value] if key.starts_with("--") => Ok((key.as_str(), value.as_str())),
+            _ => Err(USAGE.to_string()),
+        })
+        .collect()
+}
+
+fn average_spawn_points(
+    config: &GameConfig,
+    templates: &Templates,
+    themes: &Themes,
+    seed: u64,
+) -> Vec<f32> {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    (0..config.levels)
+        .map(|depth| {
+            let total: usize = (0..MAP_SAMPLES)
+                .map(|_| {
+                    let mut map_builder = crate::build_map(&mut rng, config, themes, depth);
+                    if depth == config.amulet_level && templates.find_boss(depth).is_some() {
+                        apply_arena(&mut map_builder);
+                    }
+                    map_builder.monster_spawns.len() + map_builder.treasure_spawns.len()
+                })
+                .sum();
+            total as f32 / MAP_SAMPLES as f32
+        })
+        .collect()
+}
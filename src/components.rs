@@ -1,11 +1,12 @@
 pub use crate::prelude::*;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Render {
     pub color: ColorPair,
     pub glyph: FontCharType,
+    pub order: i32,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -18,7 +19,9 @@ pub struct Player {
 pub struct Enemy;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct MovingRandomly;
+pub struct MovingRandomly {
+    pub wander_chance: f32,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToMove {
@@ -26,6 +29,12 @@ pub struct WantsToMove {
     pub destination: Point,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToDig {
+    pub entity: Entity,
+    pub target: Point,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToAttack {
     pub attacker: Entity,
@@ -41,8 +50,25 @@ pub struct Health {
 #[derive(Clone, PartialEq)]
 pub struct Name(pub String);
 
+#[derive(Clone, PartialEq)]
+pub struct Description(pub String);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ChasingPlayer;
+pub struct ChasingPlayer {
+    pub give_up_after: i32,
+    pub last_seen: Option<Point>,
+    pub lost_for: i32,
+}
+
+impl ChasingPlayer {
+    pub fn new(give_up_after: i32) -> Self {
+        Self {
+            give_up_after,
+            last_seen: None,
+            lost_for: 0,
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Item;
@@ -93,7 +119,25 @@ pub struct ActivateItem {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Damage(pub i32);
+pub struct Damage(pub Dice);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamageKind(pub DamageType);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CombatStats {
+    pub attack: i32,
+    pub defence: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Faction(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesCharm;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resistances(pub HashMap<DamageType, i32>);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Weapon;
@@ -104,6 +148,17 @@ pub struct Durability(pub i32);
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ProvidesDigging;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tunnelling {
+    pub detour: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ReduceDurability {
     pub entity: Entity,
@@ -113,3 +168,67 @@ pub struct ReduceDurability {
 pub struct ProvidesVision {
     pub amount: i32,
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AbilityKind {
+    Firebolt { damage: i32 },
+    Breath { damage: i32 },
+    Summon { template: String, limit: i32 },
+    Heal { amount: i32 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ability {
+    pub kind: AbilityKind,
+    pub range: i32,
+    pub cooldown: i32,
+    pub ready_in: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Abilities(pub Vec<Ability>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UsedAbility;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summoned(pub Entity);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pack {
+    pub id: Entity,
+    pub alerted: bool,
+    pub last_seen: Option<Point>,
+    pub memory: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boss;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alertness {
+    Asleep,
+    Unaware,
+    Alert,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Awareness {
+    pub alertness: Alertness,
+    pub perception: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub pos: Point,
+    pub volume: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BossPhase {
+    pub health_percent: i32,
+    pub abilities: Vec<Ability>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BossPhases(pub Vec<BossPhase>);
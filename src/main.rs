@@ -1,6 +1,8 @@
+mod cli;
 mod components;
 mod map_builder;
 mod resources;
+mod rules;
 mod spawner;
 mod systems;
 
@@ -16,6 +18,7 @@ mod prelude {
     pub use crate::components::*;
     pub use crate::map_builder::*;
     pub use crate::resources::*;
+    pub use crate::rules::*;
     pub use crate::spawner::*;
     pub use crate::systems::*;
 }
@@ -31,22 +34,40 @@ struct State {
 }
 
 impl State {
-    fn new() -> Self {
+    fn new(config: GameConfig, templates: Templates, themes: Themes) -> Self {
         let mut ecs = World::default();
         let mut resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut ecs, map_builder.player_start);
+        let difficulty = config.difficulty();
+        let mut map_builder = build_map(&mut rng, &config, &themes, 0);
+        spawn_player(&mut ecs, map_builder.player_start, &difficulty);
 
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut ecs, &mut rng, 0, &map_builder.monster_spawns);
+        place_goal(
+            &mut ecs,
+            &templates,
+            &config,
+            &mut map_builder,
+            &mut rng,
+            0,
+            false,
+        );
+        spawn_level(&mut ecs, &templates, &mut rng, 0, &map_builder);
 
+        resources.insert(Camera::new(map_builder.player_start, &map_builder.map));
+        resources.insert(LightMap::new(
+            &map_builder.map,
+            map_builder.theme.ambient.unwrap_or(1.0),
+        ));
         resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
         resources.insert(TurnState::AwaitingInput);
         resources.insert(map_builder.theme);
         resources.insert(Timer::new());
+        resources.insert(Inspect::default());
+        resources.insert(CombatLog::default());
+        resources.insert(TemplateStatus::default());
+        resources.insert(templates);
+        resources.insert(themes);
+        resources.insert(config);
 
         Self {
             ecs,
@@ -57,29 +78,75 @@ impl State {
         }
     }
 
-    fn reset_game_state(&mut self) {
+    fn reset_game_state(&mut self, difficulty: DifficultyLevel) {
+        let mut templates = self.resources.remove::<Templates>().unwrap();
+        let themes = self.resources.remove::<Themes>().unwrap();
+        let previous = self.resources.remove::<GameConfig>().unwrap();
         self.ecs = World::default();
         self.resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut self.ecs, map_builder.player_start);
-        // spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut self.ecs, &mut rng, 0, &map_builder.monster_spawns);
+        let mut config = GameConfig::load().unwrap_or_else(|errors| {
+            errors.iter().for_each(|error| eprintln!("{}", error));
+            previous
+        });
+        config.difficulty = difficulty;
+        templates.difficulty = config.difficulty();
+        let mut map_builder = build_map(&mut rng, &config, &themes, 0);
+        spawn_player(
+            &mut self.ecs,
+            map_builder.player_start,
+            &config.difficulty(),
+        );
+        place_goal(
+            &mut self.ecs,
+            &templates,
+            &config,
+            &mut map_builder,
+            &mut rng,
+            0,
+            false,
+        );
+        spawn_level(&mut self.ecs, &templates, &mut rng, 0, &map_builder);
+        self.resources
+            .insert(Camera::new(map_builder.player_start, &map_builder.map));
+        self.resources.insert(LightMap::new(
+            &map_builder.map,
+            map_builder.theme.ambient.unwrap_or(1.0),
+        ));
         self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
         self.resources.insert(TurnState::AwaitingInput);
         self.resources.insert(map_builder.theme);
         self.resources.insert(Timer::new());
+        self.resources.insert(Inspect::default());
+        self.resources.insert(CombatLog::default());
+        self.resources.insert(TemplateStatus::default());
+        self.resources.insert(templates);
+        self.resources.insert(themes);
+        self.resources.insert(config);
+    }
+
+    fn restart_on_key(&mut self, key: Option<VirtualKeyCode>) {
+        let difficulty = match key {
+            Some(VirtualKeyCode::Key1) => self.resources.get::<GameConfig>().unwrap().difficulty,
+            Some(VirtualKeyCode::E) => DifficultyLevel::Easy,
+            Some(VirtualKeyCode::N) => DifficultyLevel::Normal,
+            Some(VirtualKeyCode::H) => DifficultyLevel::Hard,
+            Some(VirtualKeyCode::M) => DifficultyLevel::Nightmare,
+            _ => return,
+        };
+        self.reset_game_state(difficulty);
     }
 
     fn game_over(&mut self, ctx: &mut BTerm) {
         let mut draw_batch = DrawBatch::new();
         draw_batch.target(2);
         draw_batch.print_color_centered(2, "Your quest has ended.", ColorPair::new(RED, BLACK));
+        let difficulty = self.resources.get::<GameConfig>().unwrap().difficulty;
         <&Player>::query().iter(&self.ecs).for_each(|player| {
-            let achieved_score_str = format!("You achieved a score of {}!", player.score);
+            let achieved_score_str = format!(
+                "You achieved a score of {} on {:?}!",
+                player.score, difficulty
+            );
             draw_batch.print_color_centered(3, achieved_score_str, ColorPair::new(YELLOW, BLACK));
         });
 
@@ -106,10 +173,13 @@ impl State {
             ColorPair::new(YELLOW, BLACK),
         );
         draw_batch.print_color_centered(10, "Press 1 to play again.", ColorPair::new(GREEN, BLACK));
+        draw_batch.print_color_centered(
+            11,
+            "Press E, N, H or M to play again on Easy, Normal, Hard or Nightmare.",
+            ColorPair::new(GREEN, BLACK),
+        );
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
-        }
+        self.restart_on_key(ctx.key);
 
         draw_batch.submit(0).expect("Batch error");
     }
@@ -118,11 +188,14 @@ impl State {
         let mut draw_batch = DrawBatch::new();
         draw_batch.target(2);
         draw_batch.print_color_centered(2, "You have won!", ColorPair::new(GREEN, BLACK));
+        let config = self.resources.get::<GameConfig>().unwrap();
+        let (victory_bonus, difficulty) = (config.score.victory_bonus, config.difficulty);
+        drop(config);
         <&Player>::query().iter(&self.ecs).for_each(|player| {
-            const SCORE_FOR_VICTORY: u32 = 50000;
             let achieved_score_str = format!(
-                "You achieved a score of {}!",
-                player.score + SCORE_FOR_VICTORY
+                "You achieved a score of {} on {:?}!",
+                player.score + victory_bonus,
+                difficulty
             );
             draw_batch.print_color_centered(3, achieved_score_str, ColorPair::new(GOLD, BLACK));
         });
@@ -144,10 +217,13 @@ impl State {
             ColorPair::new(WHITE, BLACK),
         );
         draw_batch.print_color_centered(9, "Press 1 to play again.", ColorPair::new(GREEN, BLACK));
+        draw_batch.print_color_centered(
+            10,
+            "Press E, N, H or M to play again on Easy, Normal, Hard or Nightmare.",
+            ColorPair::new(GREEN, BLACK),
+        );
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
-        }
+        self.restart_on_key(ctx.key);
 
         draw_batch.submit(0).expect("Batch error");
     }
@@ -182,41 +258,107 @@ impl State {
             .iter_mut(&mut self.ecs)
             .for_each(|fov| fov.is_dirty = true);
 
+        let config = self.resources.get::<GameConfig>().unwrap();
+        let ascending = config.is_escape()
+            && <&Carried>::query()
+                .filter(component::<AmuletOfYala>())
+                .iter(&self.ecs)
+                .any(|carried| carried.0 == player_entity);
+
+        let map_level = <&mut Player>::query()
+            .iter_mut(&mut self.ecs)
+            .map(|player| {
+                if ascending {
+                    player.map_level -= 1;
+                } else {
+                    player.map_level += 1;
+                }
+                player.score += config.score.level_bonus;
+                player.map_level
+            })
+            .next()
+            .unwrap() as usize;
+
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let themes = self.resources.get::<Themes>().unwrap();
+        let mut map_builder = build_map(&mut rng, &config, &themes, map_level);
+        drop(themes);
 
-        let mut map_level = 0;
-        <(&mut Player, &mut Point)>::query()
+        <&mut Point>::query()
+            .filter(component::<Player>())
             .iter_mut(&mut self.ecs)
-            .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
-                player.score += 10000;
+            .for_each(|pos| {
                 pos.x = map_builder.player_start.x;
                 pos.y = map_builder.player_start.y;
             });
 
-        if map_level == 2 {
-            spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start)
-        } else {
-            let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-            map_builder.map.tiles[exit_idx] = TileType::Exit;
-        }
-
-        spawn_level(
+        let templates = self.resources.get::<Templates>().unwrap();
+        place_goal(
             &mut self.ecs,
+            &templates,
+            &config,
+            &mut map_builder,
             &mut rng,
-            map_level as usize,
-            &map_builder.monster_spawns,
+            map_level,
+            ascending,
         );
+        spawn_level(&mut self.ecs, &templates, &mut rng, map_level, &map_builder);
+        drop(templates);
+        drop(config);
+        self.resources
+            .insert(Camera::new(map_builder.player_start, &map_builder.map));
+        self.resources.insert(LightMap::new(
+            &map_builder.map,
+            map_builder.theme.ambient.unwrap_or(1.0),
+        ));
         self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
         self.resources.insert(TurnState::AwaitingInput);
         self.resources.insert(map_builder.theme);
         // self.resources.insert(Timer::new()); TODO: Timer is dungeon timer, not per level...maybe have two timers in future?
     }
 }
 
+fn build_map(
+    rng: &mut RandomNumberGenerator,
+    config: &GameConfig,
+    themes: &Themes,
+    map_level: usize,
+) -> MapBuilder {
+    let level = config.level(map_level);
+    let mut map_builder = MapBuilder::new(rng, &level, themes);
+    if level.map.is_none() {
+        map_builder.scale_monster_spawns(config.difficulty().monster_count, rng);
+    }
+    map_builder
+}
+
+fn place_goal(
+    ecs: &mut World,
+    templates: &Templates,
+    config: &GameConfig,
+    map_builder: &mut MapBuilder,
+    rng: &mut RandomNumberGenerator,
+    map_level: usize,
+    ascending: bool,
+) {
+    let mut exit_pos = map_builder.amulet_start;
+    if !ascending && map_level == config.amulet_level {
+        if let Some(boss) = templates.find_boss(map_level) {
+            apply_arena(map_builder);
+            templates.spawn_boss(ecs, boss, map_builder.amulet_start);
+        } else {
+            spawn_amulet_of_yala(ecs, map_builder.amulet_start);
+        }
+        let player_start = map_builder.player_start;
+        exit_pos = map_builder.random_spawn_location(&player_start, rng);
+    }
+
+    if config.is_escape() || map_level + 1 < config.levels {
+        let exit_idx = map_builder.map.point2d_to_index(exit_pos);
+        map_builder.map.tiles[exit_idx] = TileType::Exit;
+    }
+}
+
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
         ctx.set_active_console(0);
@@ -230,6 +372,11 @@ impl GameState for State {
         self.resources.insert(ctx.frame_time_ms);
         ctx.set_active_console(0);
         self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        self.resources.insert(MouseClick(ctx.left_click));
+        self.resources.insert(KeyModifiers {
+            shift: ctx.shift,
+            control: ctx.control,
+        });
 
         let current_state = *self.resources.get::<TurnState>().unwrap();
         match current_state {
@@ -251,6 +398,35 @@ impl GameState for State {
 }
 
 fn main() -> BError {
+    let config = match GameConfig::load() {
+        Ok(config) => config,
+        Err(errors) => {
+            errors.iter().for_each(|error| eprintln!("{}", error));
+            return Err(format!("{} config error(s)", errors.len()).into());
+        }
+    };
+    let templates = match Templates::load(&config) {
+        Ok(templates) => templates,
+        Err(errors) => {
+            errors.iter().for_each(|error| eprintln!("{}", error));
+            return Err(format!("{} template error(s)", errors.len()).into());
+        }
+    };
+    let themes = match Themes::load(&config, &templates) {
+        Ok(themes) => themes,
+        Err(errors) => {
+            errors.iter().for_each(|error| eprintln!("{}", error));
+            return Err(format!("{} theme error(s)", errors.len()).into());
+        }
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::Command::parse(&args) {
+        Ok(Some(command)) => return command.run(&config, &templates, &themes),
+        Ok(None) => {}
+        Err(usage) => return Err(usage.into()),
+    }
+
     let context = BTermBuilder::new()
         .with_title("Dun-Jun")
         .with_fps_cap(30.0)
@@ -263,5 +439,5 @@ fn main() -> BError {
         .with_simple_console_no_bg(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
         .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, "terminal8x8.png")
         .build()?;
-    main_loop(context, State::new())
+    main_loop(context, State::new(config, templates, themes))
 }
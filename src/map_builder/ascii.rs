@@ -0,0 +1,129 @@
+use crate::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+
+const PNG_SCALE: u32 = 8;
+
+impl MapBuilder {
+    pub fn from_ascii(source: &str) -> Result<Self, String> {
+        let width = source
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = source.lines().count();
+        if width == 0 {
+            return Err("map is empty".to_string());
+        }
+        let mut mb = MapBuilder {
+            map: Map::new(width as i32, height as i32),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Theme::default(),
+        };
+        mb.fill(TileType::Wall);
+
+        let mut player_start = None;
+        let mut amulet_start = None;
+        for (y, line) in source.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pt = Point::new(x as i32, y as i32);
+                let idx = mb
+                    .map
+                    .try_idx(pt)
+                    .ok_or_else(|| format!("{} at {},{} is outside the map", c, x, y))?;
+                mb.map.tiles[idx] = match c {
+                    '#' => TileType::Wall,
+                    '>' => TileType::Exit,
+                    '.' | ' ' => TileType::Floor,
+                    '@' => {
+                        player_start = Some(pt);
+                        TileType::Floor
+                    }
+                    'A' => {
+                        amulet_start = Some(pt);
+                        TileType::Floor
+                    }
+                    'M' => {
+                        mb.monster_spawns.push(pt);
+                        TileType::Floor
+                    }
+                    '$' => {
+                        mb.treasure_spawns.push(pt);
+                        TileType::Floor
+                    }
+                    _ => return Err(format!("unknown tile '{}' at {},{}", c, x, y)),
+                };
+            }
+        }
+
+        mb.player_start = player_start.ok_or("map has no player start (@)")?;
+        mb.amulet_start = match amulet_start {
+            Some(amulet_start) => amulet_start,
+            None => mb.find_most_distant(),
+        };
+        Ok(mb)
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::new();
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                ascii.push(self.cell(Point::new(x, y)));
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    pub fn to_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (width, height) = (self.map.width as u32, self.map.height as u32);
+        let mut data = Vec::with_capacity((width * height * PNG_SCALE * PNG_SCALE * 3) as usize);
+        for py in 0..height * PNG_SCALE {
+            for px in 0..width * PNG_SCALE {
+                let pt = Point::new((px / PNG_SCALE) as i32, (py / PNG_SCALE) as i32);
+                let (r, g, b) = match self.cell(pt) {
+                    '#' => DIM_GRAY,
+                    '>' => CYAN,
+                    '@' => YELLOW,
+                    'A' => MAGENTA,
+                    'M' => RED,
+                    '$' => GOLD,
+                    _ => BLACK,
+                };
+                data.extend_from_slice(&[r, g, b]);
+            }
+        }
+
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            width * PNG_SCALE,
+            height * PNG_SCALE,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+
+    fn cell(&self, pt: Point) -> char {
+        if pt == self.player_start {
+            '@'
+        } else if pt == self.amulet_start {
+            'A'
+        } else if self.monster_spawns.contains(&pt) {
+            'M'
+        } else if self.treasure_spawns.contains(&pt) {
+            '$'
+        } else {
+            match self.map.tiles[self.map.point2d_to_index(pt)] {
+                TileType::Wall => '#',
+                TileType::Floor => '.',
+                TileType::Exit => '>',
+            }
+        }
+    }
+}
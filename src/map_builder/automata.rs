@@ -4,14 +4,20 @@ use crate::prelude::*;
 pub struct CellularAutomataArchitect {}
 
 impl MapArchitect for CellularAutomataArchitect {
-    fn new_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new_map_builder(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
-            theme: super::themes::DungeonTheme::new(),
+            theme: Theme::default(),
         };
 
         self.random_noise_map(rng, &mut mb.map);
@@ -20,6 +26,8 @@ impl MapArchitect for CellularAutomataArchitect {
         }
 
         let start = self.find_start(&mb.map);
+        let start_idx = mb.map.point2d_to_index(start);
+        mb.map.tiles[start_idx] = TileType::Floor;
         mb.monster_spawns = mb.spawn_monsters(&start, rng);
         mb.player_start = start;
         mb.amulet_start = mb.find_most_distant();
@@ -43,7 +51,7 @@ impl CellularAutomataArchitect {
         let mut neighbors = 0;
         for iy in -1..=1 {
             for ix in -1..=1 {
-                if !(ix == 0 && iy == 0) && map.tiles[map_idx(x + ix, y + iy)] == TileType::Wall {
+                if !(ix == 0 && iy == 0) && map.tiles[map.idx(x + ix, y + iy)] == TileType::Wall {
                     neighbors += 1;
                 }
             }
@@ -54,10 +62,10 @@ impl CellularAutomataArchitect {
 
     fn iteration(&mut self, map: &mut Map) {
         let mut new_tiles = map.tiles.clone();
-        for y in 1..SCREEN_HEIGHT - 1 {
-            for x in 1..SCREEN_WIDTH - 1 {
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
                 let neighbors = self.count_neighbors(x, y, map);
-                let idx = map_idx(x, y);
+                let idx = map.idx(x, y);
                 if neighbors == 0 || neighbors > 4 {
                     new_tiles[idx] = TileType::Wall;
                 } else {
@@ -69,12 +77,22 @@ impl CellularAutomataArchitect {
     }
 
     fn find_start(&self, map: &Map) -> Point {
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = Point::new(map.width / 2, map.height / 2);
         let closest_point = map
             .tiles
             .iter()
             .enumerate()
             .filter(|(_, t)| **t == TileType::Floor)
+            .filter(|(idx, _)| {
+                let pt = map.index_to_point2d(*idx);
+                pt.x > 0
+                    && pt.x < map.width - 1
+                    && pt.y > 0
+                    && pt.y < map.height - 1
+                    && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .any(|(dx, dy)| map.tiles[map.idx(pt.x + dx, pt.y + dy)] == TileType::Floor)
+            })
             .map(|(idx, _)| {
                 (
                     idx,
@@ -83,7 +101,7 @@ impl CellularAutomataArchitect {
             })
             .min_by(|(_, distance1), (_, distance2)| distance1.partial_cmp(distance2).unwrap())
             .map(|(idx, _)| idx)
-            .unwrap();
+            .unwrap_or_else(|| map.point2d_to_index(center));
 
         map.index_to_point2d(closest_point)
     }
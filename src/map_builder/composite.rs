@@ -0,0 +1,136 @@
+use super::prefab::apply_prefab;
+use crate::prelude::*;
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub enum BuildStep {
+    Base(ArchitectKind),
+    Regions(Vec<ArchitectKind>),
+    ErodeWalls(f32),
+    AddPrefab,
+    CullUnreachable,
+}
+
+impl MapBuilder {
+    pub(super) fn build(
+        steps: &[BuildStep],
+        rng: &mut RandomNumberGenerator,
+        level: &LevelConfig,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
+        let mut mb = MapBuilder::from_step(&steps[0], rng, level, width, height);
+        steps.iter().skip(1).for_each(|step| match step {
+            BuildStep::Base(_) | BuildStep::Regions(_) => {
+                mb = MapBuilder::from_step(step, rng, level, width, height)
+            }
+            BuildStep::ErodeWalls(chance) => mb.erode_walls(*chance, rng),
+            BuildStep::AddPrefab => apply_prefab(&mut mb, rng),
+            BuildStep::CullUnreachable => mb.cull_unreachable(),
+        });
+        mb
+    }
+
+    fn from_step(
+        step: &BuildStep,
+        rng: &mut RandomNumberGenerator,
+        level: &LevelConfig,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
+        match step {
+            BuildStep::Base(kind) => {
+                MapBuilder::architect(*kind, level).new_map_builder(rng, width, height)
+            }
+            BuildStep::Regions(kinds) => MapBuilder::from_regions(kinds, rng, level, width, height),
+            _ => panic!("{:?} cannot start a map", step),
+        }
+    }
+
+    fn from_regions(
+        kinds: &[ArchitectKind],
+        rng: &mut RandomNumberGenerator,
+        level: &LevelConfig,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Theme::default(),
+        };
+        mb.fill(TileType::Wall);
+
+        let region_width = width / kinds.len() as i32;
+        let mut starts = Vec::new();
+        for (i, kind) in kinds.iter().enumerate() {
+            let offset = Point::new(i as i32 * region_width, 0);
+            let sub_width = if i + 1 == kinds.len() {
+                width - offset.x
+            } else {
+                region_width
+            };
+            let mut sub =
+                MapBuilder::architect(*kind, level).new_map_builder(rng, sub_width, height);
+            sub.connect_regions();
+            sub.detect_rooms();
+
+            sub.map.tiles.iter().enumerate().for_each(|(idx, tile)| {
+                let pt = sub.map.index_to_point2d(idx) + offset;
+                let idx = mb.map.point2d_to_index(pt);
+                mb.map.tiles[idx] = *tile;
+            });
+            mb.rooms.extend(sub.rooms.iter().map(|sub_room| {
+                let area = &sub_room.area;
+                let mut room = Room::new(Rect::with_exact(
+                    area.x1 + offset.x,
+                    area.y1,
+                    area.x2 + offset.x,
+                    area.y2,
+                ));
+                room.tiles = sub_room.tiles.iter().map(|pt| *pt + offset).collect();
+                room
+            }));
+            mb.monster_spawns
+                .extend(sub.monster_spawns.iter().map(|pt| *pt + offset));
+            mb.treasure_spawns
+                .extend(sub.treasure_spawns.iter().map(|pt| *pt + offset));
+            starts.push(sub.player_start + offset);
+        }
+
+        for pair in starts.windows(2) {
+            mb.apply_horizontal_tunnel(pair[0].x, pair[1].x, pair[0].y);
+            mb.apply_vertical_tunnel(pair[0].y, pair[1].y, pair[1].x);
+        }
+        mb.player_start = starts[0];
+        let player_start = mb.player_start;
+        mb.monster_spawns.retain(|pt| *pt != player_start);
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+
+    fn erode_walls(&mut self, chance: f32, rng: &mut RandomNumberGenerator) {
+        let eroded: Vec<usize> = (0..self.map.tiles.len())
+            .filter(|idx| self.map.tiles[*idx] == TileType::Wall)
+            .filter(|idx| {
+                let pt = self.map.index_to_point2d(*idx);
+                pt.x > 0
+                    && pt.x < self.map.width - 1
+                    && pt.y > 0
+                    && pt.y < self.map.height - 1
+                    && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .any(|(dx, dy)| self.map.can_enter_tile(pt + Point::new(*dx, *dy)))
+            })
+            .collect();
+        eroded.into_iter().for_each(|idx| {
+            if rng.range(0.0, 1.0) < chance {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        });
+    }
+}
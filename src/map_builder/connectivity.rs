@@ -0,0 +1,195 @@
+use crate::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+const MIN_REGION_SIZE: usize = 12;
+const UNREACHABLE: f32 = 2000.0;
+
+impl MapBuilder {
+    pub fn connect_regions(&mut self) {
+        loop {
+            let reachable = self.reachable_from_start();
+            let unreachable = (0..self.map.tiles.len()).find(|idx| {
+                self.map.can_enter_tile(self.map.index_to_point2d(*idx))
+                    && reachable.map[*idx] >= UNREACHABLE
+            });
+            let start = match unreachable {
+                Some(idx) => self.map.index_to_point2d(idx),
+                None => break,
+            };
+
+            let mut region: Vec<Point> = self.region(start).into_iter().collect();
+            region.sort_by_key(|pt| self.map.point2d_to_index(*pt));
+            if region.len() < MIN_REGION_SIZE {
+                region.iter().for_each(|pt| {
+                    let idx = self.map.point2d_to_index(*pt);
+                    self.map.tiles[idx] = TileType::Wall;
+                });
+                continue;
+            }
+
+            let connected: Vec<Point> = reachable
+                .map
+                .iter()
+                .enumerate()
+                .filter(|(_, distance)| **distance < UNREACHABLE)
+                .map(|(idx, _)| self.map.index_to_point2d(idx))
+                .collect();
+            let (from, to) = region
+                .iter()
+                .flat_map(|from| connected.iter().map(move |to| (*from, *to)))
+                .min_by(|a, b| {
+                    let da = DistanceAlg::Manhattan.distance2d(a.0, a.1);
+                    let db = DistanceAlg::Manhattan.distance2d(b.0, b.1);
+                    da.partial_cmp(&db).unwrap()
+                })
+                .unwrap();
+            self.apply_horizontal_tunnel(from.x, to.x, from.y);
+            self.apply_vertical_tunnel(from.y, to.y, to.x);
+        }
+
+        let reachable = self.reachable_from_start();
+        let map = &self.map;
+        self.monster_spawns.retain(|pt| {
+            map.in_bounds(*pt) && reachable.map[map.point2d_to_index(*pt)] < UNREACHABLE
+        });
+        self.treasure_spawns.retain(|pt| {
+            map.in_bounds(*pt) && reachable.map[map.point2d_to_index(*pt)] < UNREACHABLE
+        });
+        if reachable.map[self.map.point2d_to_index(self.amulet_start)] >= UNREACHABLE {
+            self.amulet_start = self.find_most_distant();
+        }
+    }
+
+    pub fn cull_unreachable(&mut self) {
+        let reachable = self.reachable_from_start();
+        self.map
+            .tiles
+            .iter_mut()
+            .enumerate()
+            .for_each(|(idx, tile)| {
+                if *tile == TileType::Floor && reachable.map[idx] >= UNREACHABLE {
+                    *tile = TileType::Wall;
+                }
+            });
+        let map = &self.map;
+        self.monster_spawns.retain(|pt| map.can_enter_tile(*pt));
+        self.treasure_spawns.retain(|pt| map.can_enter_tile(*pt));
+        if !self.map.can_enter_tile(self.amulet_start) {
+            self.amulet_start = self.find_most_distant();
+        }
+    }
+
+    pub fn connectivity_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.map.can_enter_tile(self.player_start) {
+            errors.push(format!(
+                "player start {:?} is not walkable",
+                self.player_start
+            ));
+            return errors;
+        }
+
+        let reachable = self.reachable_from_start();
+        let is_reachable = |pt: Point| {
+            self.map.in_bounds(pt) && reachable.map[self.map.point2d_to_index(pt)] < UNREACHABLE
+        };
+        if !is_reachable(self.amulet_start) {
+            errors.push(format!("amulet {:?} is unreachable", self.amulet_start));
+        }
+        self.map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == TileType::Exit)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| !is_reachable(*pt))
+            .for_each(|pt| errors.push(format!("exit {:?} is unreachable", pt)));
+        self.monster_spawns
+            .iter()
+            .filter(|pt| !is_reachable(**pt))
+            .for_each(|pt| errors.push(format!("spawn {:?} is unreachable", pt)));
+        self.treasure_spawns
+            .iter()
+            .filter(|pt| !is_reachable(**pt))
+            .for_each(|pt| errors.push(format!("treasure {:?} is unreachable", pt)));
+        if self.monster_spawns.contains(&self.player_start) {
+            errors.push("a spawn point is on the player start".to_string());
+        }
+        errors
+    }
+
+    pub(super) fn reachable_from_start(&self) -> DijkstraMap {
+        let start = self.map.point2d_to_index(self.player_start);
+        let mut dijkstra_map =
+            DijkstraMap::new(self.map.width, self.map.height, &[start], &self.map, 1024.0);
+        dijkstra_map.map[start] = 0.0;
+        dijkstra_map
+    }
+
+    fn region(&self, start: Point) -> HashSet<Point> {
+        let mut region = HashSet::new();
+        let mut open = VecDeque::from(vec![start]);
+        while let Some(pt) = open.pop_front() {
+            if !self.map.can_enter_tile(pt) || !region.insert(pt) {
+                continue;
+            }
+            open.extend(
+                [
+                    Point::new(-1, 0),
+                    Point::new(1, 0),
+                    Point::new(0, -1),
+                    Point::new(0, 1),
+                ]
+                .iter()
+                .map(|delta| pt + *delta),
+            );
+        }
+        region
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: u64 = 10;
+    const ARCHITECTS: [ArchitectKind; 6] = [
+        ArchitectKind::Rooms,
+        ArchitectKind::CellularAutomata,
+        ArchitectKind::DrunkardsWalk,
+        ArchitectKind::WaveFunctionCollapse,
+        ArchitectKind::Maze,
+        ArchitectKind::Empty,
+    ];
+
+    #[test]
+    fn generated_maps_are_connected() {
+        let config = GameConfig::load().unwrap();
+        let templates = Templates::load(&config).unwrap();
+        let themes = Themes::load(&config, &templates).unwrap();
+        for architect in ARCHITECTS.iter() {
+            let level = LevelConfig {
+                architect: Some(*architect),
+                ..Default::default()
+            };
+            for seed in 0..SEEDS {
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let mb = MapBuilder::new(&mut rng, &level, &themes);
+                assert_eq!(
+                    mb.connectivity_errors(),
+                    Vec::<String>::new(),
+                    "{:?} seed {}",
+                    architect,
+                    seed
+                );
+                let reachable = mb.reachable_from_start();
+                assert!(
+                    reachable.map[mb.map.point2d_to_index(mb.amulet_start)] < UNREACHABLE,
+                    "{:?} seed {}: amulet unreachable",
+                    architect,
+                    seed
+                );
+            }
+        }
+    }
+}
@@ -2,24 +2,29 @@ use super::MapArchitect;
 use crate::prelude::*;
 
 const STAGGER_DISTANCE: usize = 400;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-const DESIRED_FLOOR: usize = NUM_TILES * 3 / 4;
 
 pub struct DrunkardsWalkArchitect {}
 
 impl MapArchitect for DrunkardsWalkArchitect {
-    fn new_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new_map_builder(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
-            theme: super::themes::DungeonTheme::new(),
+            theme: Theme::default(),
         };
         mb.fill(TileType::Wall);
 
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = Point::new(width / 2, height / 2);
+        let desired_floor = mb.map.tiles.len() * 3 / 4;
         self.drunkard(&center, rng, &mut mb.map);
 
         while mb
@@ -28,16 +33,16 @@ impl MapArchitect for DrunkardsWalkArchitect {
             .iter()
             .filter(|t| **t == TileType::Floor)
             .count()
-            < DESIRED_FLOOR
+            < desired_floor
         {
             self.drunkard(
-                &Point::new(rng.range(0, SCREEN_WIDTH), rng.range(0, SCREEN_HEIGHT)),
+                &Point::new(rng.range(0, width), rng.range(0, height)),
                 rng,
                 &mut mb.map,
             );
             let dijkstra_map = DijkstraMap::new(
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
+                width,
+                height,
                 &[mb.map.point2d_to_index(center)],
                 &mb.map,
                 1024.0,
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+const SOIL: f32 = -0.3;
+const GRANITE: f32 = 0.35;
+
+impl MapBuilder {
+    pub fn assign_materials(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut noise = FastNoise::seeded(rng.next_u64());
+        noise.set_noise_type(NoiseType::Simplex);
+        noise.set_frequency(0.08);
+
+        let map = &mut self.map;
+        for idx in 0..map.tiles.len() {
+            let pt = map.index_to_point2d(idx);
+            if map.materials[idx] == Material::Bedrock {
+                continue;
+            }
+            map.materials[idx] =
+                if pt.x == 0 || pt.y == 0 || pt.x == map.width - 1 || pt.y == map.height - 1 {
+                    Material::Bedrock
+                } else {
+                    match noise.get_noise(pt.x as f32, pt.y as f32) {
+                        n if n < SOIL => Material::Soil,
+                        n if n < GRANITE => Material::Stone,
+                        _ => Material::Granite,
+                    }
+                };
+        }
+    }
+}
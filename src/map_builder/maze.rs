@@ -0,0 +1,178 @@
+use super::{MapArchitect, DEFAULT_AREA};
+use crate::prelude::*;
+
+const NUM_TREASURES: f32 = 6.0;
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub struct MazeArchitect {
+    pub corridor_width: i32,
+    pub braid: f32,
+}
+
+struct Cells {
+    columns: i32,
+    rows: i32,
+    links: Vec<[bool; 4]>,
+}
+
+impl Cells {
+    fn idx(&self, x: i32, y: i32) -> usize {
+        (y * self.columns + x) as usize
+    }
+
+    fn neighbour(&self, x: i32, y: i32, direction: usize) -> Option<(i32, i32)> {
+        let (nx, ny) = (x + DIRECTIONS[direction].0, y + DIRECTIONS[direction].1);
+        if nx >= 0 && nx < self.columns && ny >= 0 && ny < self.rows {
+            Some((nx, ny))
+        } else {
+            None
+        }
+    }
+
+    fn link(&mut self, x: i32, y: i32, direction: usize) {
+        if let Some((nx, ny)) = self.neighbour(x, y, direction) {
+            let (from, to) = (self.idx(x, y), self.idx(nx, ny));
+            self.links[from][direction] = true;
+            self.links[to][(direction + 2) % 4] = true;
+        }
+    }
+
+    fn is_dead_end(&self, idx: usize) -> bool {
+        self.links[idx].iter().filter(|linked| **linked).count() == 1
+    }
+}
+
+impl MapArchitect for MazeArchitect {
+    fn new_map_builder(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Theme::default(),
+        };
+        mb.fill(TileType::Wall);
+
+        let pitch = self.corridor_width + 1;
+        let mut cells = Cells {
+            columns: i32::max(1, (width - 1) / pitch),
+            rows: i32::max(1, (height - 1) / pitch),
+            links: Vec::new(),
+        };
+        cells.links = vec![[false; 4]; (cells.columns * cells.rows) as usize];
+        let start = (cells.columns / 2, cells.rows / 2);
+        self.backtrack(&mut cells, start, rng);
+        self.add_loops(&mut cells, rng);
+        self.carve(&cells, &mut mb.map);
+
+        mb.player_start = self.cell_center(start);
+        mb.amulet_start = mb.find_most_distant();
+
+        let dijkstra_map = mb.reachable_from_start();
+        let mut dead_ends: Vec<Point> = (0..cells.rows)
+            .flat_map(|y| (0..cells.columns).map(move |x| (x, y)))
+            .filter(|(x, y)| cells.is_dead_end(cells.idx(*x, *y)) && (*x, *y) != start)
+            .map(|cell| self.cell_center(cell))
+            .filter(|pt| *pt != mb.amulet_start)
+            .collect();
+        dead_ends.sort_by(|a, b| {
+            let da = dijkstra_map.map[mb.map.point2d_to_index(*a)];
+            let db = dijkstra_map.map[mb.map.point2d_to_index(*b)];
+            db.partial_cmp(&da).unwrap()
+        });
+        let area_scale = (width * height) as f32 / DEFAULT_AREA;
+        dead_ends.truncate(usize::max(1, (NUM_TREASURES * area_scale).round() as usize));
+        mb.treasure_spawns = dead_ends;
+
+        mb.monster_spawns = mb.spawn_monsters(&mb.player_start, rng);
+        let treasure = &mb.treasure_spawns;
+        mb.monster_spawns.retain(|pt| !treasure.contains(pt));
+        mb
+    }
+}
+
+impl MazeArchitect {
+    fn cell_center(&self, (x, y): (i32, i32)) -> Point {
+        let pitch = self.corridor_width + 1;
+        Point::new(
+            1 + x * pitch + (self.corridor_width - 1) / 2,
+            1 + y * pitch + (self.corridor_width - 1) / 2,
+        )
+    }
+
+    fn backtrack(&self, cells: &mut Cells, start: (i32, i32), rng: &mut RandomNumberGenerator) {
+        let mut visited = vec![false; cells.links.len()];
+        visited[cells.idx(start.0, start.1)] = true;
+        let mut stack = vec![start];
+        while let Some(&(x, y)) = stack.last() {
+            let unvisited: Vec<(usize, (i32, i32))> = (0..DIRECTIONS.len())
+                .filter_map(|direction| cells.neighbour(x, y, direction).map(|n| (direction, n)))
+                .filter(|(_, (nx, ny))| !visited[cells.idx(*nx, *ny)])
+                .collect();
+            match rng.random_slice_entry(&unvisited) {
+                Some(&(direction, (nx, ny))) => {
+                    cells.link(x, y, direction);
+                    visited[cells.idx(nx, ny)] = true;
+                    stack.push((nx, ny));
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    fn add_loops(&self, cells: &mut Cells, rng: &mut RandomNumberGenerator) {
+        for y in 0..cells.rows {
+            for x in 0..cells.columns {
+                let idx = cells.idx(x, y);
+                if !cells.is_dead_end(idx) || rng.range(0.0, 1.0) >= self.braid {
+                    continue;
+                }
+                let closed: Vec<usize> = (0..DIRECTIONS.len())
+                    .filter(|direction| !cells.links[idx][*direction])
+                    .filter(|direction| cells.neighbour(x, y, *direction).is_some())
+                    .collect();
+                if let Some(&direction) = rng.random_slice_entry(&closed) {
+                    cells.link(x, y, direction);
+                }
+            }
+        }
+    }
+
+    fn carve(&self, cells: &Cells, map: &mut Map) {
+        let pitch = self.corridor_width + 1;
+        let mut floor = |x: i32, y: i32| {
+            if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
+                let idx = map.idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        };
+        for cy in 0..cells.rows {
+            for cx in 0..cells.columns {
+                let links = cells.links[cells.idx(cx, cy)];
+                let (left, top) = (1 + cx * pitch, 1 + cy * pitch);
+                for dy in 0..self.corridor_width {
+                    for dx in 0..self.corridor_width {
+                        floor(left + dx, top + dy);
+                    }
+                    if links[1] {
+                        floor(left + self.corridor_width, top + dy);
+                    }
+                }
+                if links[2] {
+                    for dx in 0..self.corridor_width {
+                        floor(left + dx, top + self.corridor_width);
+                    }
+                }
+            }
+        }
+    }
+}
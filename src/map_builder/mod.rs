@@ -1,48 +1,110 @@
 use crate::prelude::*;
+use serde::Deserialize;
+mod ascii;
+mod composite;
+pub use composite::BuildStep;
+mod connectivity;
 mod empty;
-// use empty::EmptyArchitect;
+use empty::EmptyArchitect;
 mod rooms;
 use rooms::RoomsArchitect;
 mod automata;
 use automata::CellularAutomataArchitect;
 mod drunkard;
 use drunkard::DrunkardsWalkArchitect;
+mod materials;
+mod maze;
+use maze::MazeArchitect;
 mod prefab;
-use prefab::apply_prefab;
+mod regions;
+pub use prefab::apply_arena;
+pub use regions::{Room, RoomKind};
 mod themes;
+mod wfc;
 pub use themes::*;
+pub use wfc::Sample;
+use wfc::WaveFunctionCollapseArchitect;
+
+const MAX_ATTEMPTS: usize = 10;
+const FALLBACK_ARCHITECT: ArchitectKind = ArchitectKind::Rooms;
+const DEFAULT_AREA: f32 = (SCREEN_WIDTH * SCREEN_HEIGHT) as f32;
 
 trait MapArchitect {
-    fn new_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
+    fn new_map_builder(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder;
 }
 
-pub trait MapTheme: Sync + Send {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum ArchitectKind {
+    Rooms,
+    CellularAutomata,
+    DrunkardsWalk,
+    WaveFunctionCollapse,
+    Maze,
+    Empty,
 }
 
 pub struct MapBuilder {
     pub map: Map,
-    pub rooms: Vec<Rect>,
+    pub rooms: Vec<Room>,
     pub monster_spawns: Vec<Point>,
+    pub treasure_spawns: Vec<Point>,
     pub player_start: Point,
     pub amulet_start: Point,
-    pub theme: Box<dyn MapTheme>,
+    pub theme: Theme,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(RoomsArchitect {}),
-            1 => Box::new(CellularAutomataArchitect {}),
-            _ => Box::new(DrunkardsWalkArchitect {}),
-        };
-        let mut mb = architect.new_map_builder(rng);
-        apply_prefab(&mut mb, rng);
+    pub fn new(rng: &mut RandomNumberGenerator, level: &LevelConfig, themes: &Themes) -> Self {
+        if let Some(path) = &level.map {
+            let mut mb = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| MapBuilder::from_ascii(&source))
+                .unwrap_or_else(|e| panic!("Unable to load map {}: {}", path, e));
+            mb.theme = themes.pick(level, None, rng);
+            mb.assign_materials(rng);
+            return mb;
+        }
 
-        mb.theme = match rng.range(0, 2) {
-            0 => DungeonTheme::new(),
-            _ => ForestTheme::new(),
-        };
+        let (width, height) = level.size.unwrap_or((SCREEN_WIDTH, SCREEN_HEIGHT));
+        let architect_kind = level.architect.unwrap_or_else(|| match rng.range(0, 4) {
+            0 => ArchitectKind::Rooms,
+            1 => ArchitectKind::CellularAutomata,
+            2 => ArchitectKind::WaveFunctionCollapse,
+            _ => ArchitectKind::DrunkardsWalk,
+        });
+        let steps = level
+            .steps
+            .clone()
+            .unwrap_or_else(|| vec![BuildStep::Base(architect_kind), BuildStep::AddPrefab]);
+        let (mut mb, architect_kind) =
+            match MapBuilder::build_connected(&steps, rng, level, width, height) {
+                Some(mb) => (mb, architect_kind),
+                None => {
+                    let fallback = [BuildStep::Base(FALLBACK_ARCHITECT)];
+                    let mb = MapBuilder::build_connected(
+                        &fallback,
+                        rng,
+                        &LevelConfig::default(),
+                        width,
+                        height,
+                    )
+                    .unwrap_or_else(|| {
+                        let empty = [BuildStep::Base(ArchitectKind::Empty)];
+                        MapBuilder::build(&empty, rng, level, width, height)
+                    });
+                    (mb, FALLBACK_ARCHITECT)
+                }
+            };
+
+        mb.detect_rooms();
+        mb.populate_rooms(rng);
+        mb.assign_materials(rng);
+        mb.theme = themes.pick(level, Some(architect_kind), rng);
 
         if let 0 = rng.range(0, 3) {
             mb.amulet_start = mb.random_spawn_location(&mb.player_start, rng);
@@ -51,18 +113,59 @@ impl MapBuilder {
         mb
     }
 
+    fn build_connected(
+        steps: &[BuildStep],
+        rng: &mut RandomNumberGenerator,
+        level: &LevelConfig,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder> {
+        (0..MAX_ATTEMPTS)
+            .map(|_| {
+                let mut mb = MapBuilder::build(steps, rng, level, width, height);
+                mb.connect_regions();
+                mb
+            })
+            .find(|mb| mb.connectivity_errors().is_empty())
+    }
+
+    fn architect(kind: ArchitectKind, level: &LevelConfig) -> Box<dyn MapArchitect> {
+        match kind {
+            ArchitectKind::Rooms => Box::new(RoomsArchitect {}),
+            ArchitectKind::CellularAutomata => Box::new(CellularAutomataArchitect {}),
+            ArchitectKind::DrunkardsWalk => Box::new(DrunkardsWalkArchitect {}),
+            ArchitectKind::WaveFunctionCollapse => Box::new(WaveFunctionCollapseArchitect::new(
+                level.loaded_sample.as_ref(),
+            )),
+            ArchitectKind::Maze => Box::new(MazeArchitect {
+                corridor_width: level.corridor_width.unwrap_or(1),
+                braid: level.braid.unwrap_or(0.0),
+            }),
+            ArchitectKind::Empty => Box::new(EmptyArchitect {}),
+        }
+    }
+
+    pub fn scale_monster_spawns(&mut self, factor: f32, rng: &mut RandomNumberGenerator) {
+        let target = (self.monster_spawns.len() as f32 * factor).round() as usize;
+        while self.monster_spawns.len() > target {
+            let idx = rng.random_slice_index(&self.monster_spawns).unwrap();
+            self.monster_spawns.remove(idx);
+        }
+        while self.monster_spawns.len() < target {
+            let spawn = self.random_spawn_location(&self.player_start, rng);
+            if self.monster_spawns.contains(&spawn) {
+                break;
+            }
+            self.monster_spawns.push(spawn);
+        }
+    }
+
     fn fill(&mut self, tile: TileType) {
         self.map.tiles.iter_mut().for_each(|t| *t = tile);
     }
 
     fn find_most_distant(&self) -> Point {
-        let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            &[self.map.point2d_to_index(self.player_start)],
-            &self.map,
-            1024.0,
-        );
+        let dijkstra_map = self.reachable_from_start();
 
         const UNREACHABLE: &f32 = &f32::MAX;
         self.map.index_to_point2d(
@@ -78,31 +181,35 @@ impl MapBuilder {
     }
 
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
-        let num_rooms = rng.range(10, 30);
-        while self.rooms.len() < num_rooms {
+        let area_scale = (self.map.width * self.map.height) as f32 / DEFAULT_AREA;
+        let num_rooms = usize::max(2, (rng.range(10, 30) as f32 * area_scale) as usize);
+        let max_size = i32::min(10, i32::min(self.map.width, self.map.height) / 3);
+        let mut attempts = 0;
+        while self.rooms.len() < num_rooms && attempts < 1000 {
+            attempts += 1;
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10),
-                rng.range(1, SCREEN_HEIGHT - 10),
-                rng.range(2, 10),
-                rng.range(2, 10),
+                rng.range(1, i32::max(2, self.map.width - max_size)),
+                rng.range(1, i32::max(2, self.map.height - max_size)),
+                rng.range(2, i32::max(3, max_size)),
+                rng.range(2, i32::max(3, max_size)),
             );
 
             let mut overlap = false;
             for r in self.rooms.iter() {
-                if r.intersect(&room) {
+                if r.area.intersect(&room) {
                     overlap = true;
                 }
             }
 
             if !overlap {
                 room.for_each(|p| {
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0 && p.y < SCREEN_HEIGHT {
-                        let idx = map_idx(p.x, p.y);
+                    if p.x > 0 && p.x < self.map.width - 1 && p.y > 0 && p.y < self.map.height - 1 {
+                        let idx = self.map.idx(p.x, p.y);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
 
-                self.rooms.push(room)
+                self.rooms.push(Room::new(room))
             }
         }
     }
@@ -127,7 +234,7 @@ impl MapBuilder {
 
     fn build_corridors(&mut self, rng: &mut RandomNumberGenerator) {
         let mut rooms = self.rooms.clone();
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|a| a.center().x);
 
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
@@ -143,8 +250,9 @@ impl MapBuilder {
         }
     }
 
-    fn random_spawn_location(&self, start: &Point, rng: &mut RandomNumberGenerator) -> Point {
-        let spawnable_tiles: Vec<Point> = self
+    pub fn random_spawn_location(&self, start: &Point, rng: &mut RandomNumberGenerator) -> Point {
+        let min_distance = self.spawn_distance();
+        let mut spawnable_tiles: Vec<Point> = self
             .map
             .tiles
             .iter()
@@ -152,20 +260,38 @@ impl MapBuilder {
             .filter(|(idx, t)| {
                 **t == TileType::Floor
                     && DistanceAlg::Pythagoras.distance2d(*start, self.map.index_to_point2d(*idx))
-                        > 10.0
+                        > min_distance
                     && !self
                         .monster_spawns
                         .contains(&self.map.index_to_point2d(*idx))
             })
             .map(|(idx, _)| self.map.index_to_point2d(idx))
             .collect();
+        if spawnable_tiles.is_empty() {
+            spawnable_tiles = self
+                .map
+                .tiles
+                .iter()
+                .enumerate()
+                .filter(|(idx, t)| {
+                    **t == TileType::Floor && self.map.index_to_point2d(*idx) != *start
+                })
+                .map(|(idx, _)| self.map.index_to_point2d(idx))
+                .collect();
+        }
 
-        let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
-        spawnable_tiles[target_index]
+        rng.random_slice_entry(&spawnable_tiles)
+            .copied()
+            .unwrap_or(*start)
+    }
+
+    fn spawn_distance(&self) -> f32 {
+        f32::min(10.0, (i32::max(self.map.width, self.map.height) / 4) as f32)
     }
 
     fn spawn_monsters(&self, start: &Point, rng: &mut RandomNumberGenerator) -> Vec<Point> {
-        const NUM_MONSTERS: usize = 50;
+        const NUM_MONSTERS: f32 = 50.0;
+        let min_distance = self.spawn_distance();
         let mut spawnable_tiles: Vec<Point> = self
             .map
             .tiles
@@ -174,13 +300,18 @@ impl MapBuilder {
             .filter(|(idx, t)| {
                 **t == TileType::Floor
                     && DistanceAlg::Pythagoras.distance2d(*start, self.map.index_to_point2d(*idx))
-                        > 10.0
+                        > min_distance
             })
             .map(|(idx, _)| self.map.index_to_point2d(idx))
             .collect();
 
+        let area_scale = (self.map.width * self.map.height) as f32 / DEFAULT_AREA;
+        let num_monsters = usize::min(
+            (NUM_MONSTERS * area_scale).round() as usize,
+            spawnable_tiles.len(),
+        );
         let mut spawns: Vec<Point> = Vec::new();
-        for _ in 0..NUM_MONSTERS {
+        for _ in 0..num_monsters {
             let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
             spawns.push(spawnable_tiles[target_index]);
             spawnable_tiles.remove(target_index);
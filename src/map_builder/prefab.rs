@@ -18,12 +18,31 @@ const FORTRESS: (&str, i32, i32) = (
     11,
 );
 
+const ARENA: (&str, i32, i32) = (
+    "
+#####-#####
+#---------#
+#-#-----#-#
+#---------#
+-----B-----
+#---------#
+#-#-----#-#
+#---------#
+#####-#####
+",
+    11,
+    9,
+);
+
 pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    if mb.map.width <= FORTRESS.1 || mb.map.height <= FORTRESS.2 {
+        return;
+    }
     let mut placement = None;
 
     let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
+        mb.map.width,
+        mb.map.height,
         &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
         1024.0,
@@ -32,8 +51,8 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
     let mut attempts = 0;
     while placement.is_none() && attempts < 10 {
         let dimensions = Rect::with_size(
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
+            rng.range(0, mb.map.width - FORTRESS.1),
+            rng.range(0, mb.map.height - FORTRESS.2),
             FORTRESS.1,
             FORTRESS.2,
         );
@@ -51,32 +70,64 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
             placement = Some(Point::new(dimensions.x1, dimensions.y1));
             let points = dimensions.point_set();
             mb.monster_spawns.retain(|pt| !points.contains(pt));
+            mb.treasure_spawns.retain(|pt| !points.contains(pt));
         }
         attempts += 1;
     }
 
     if let Some(placement) = placement {
-        let string_vec: Vec<char> = FORTRESS
-            .0
-            .chars()
-            .filter(|a| *a != '\r' && *a != '\n')
-            .collect();
-        let mut i = 0;
-        for ty in placement.y..placement.y + FORTRESS.2 {
-            for tx in placement.x..placement.x + FORTRESS.1 {
-                let idx = map_idx(tx, ty);
-                let c = string_vec[i];
-                match c {
-                    'M' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                        mb.monster_spawns.push(Point::new(tx, ty));
-                    }
-                    '-' => mb.map.tiles[idx] = TileType::Floor,
-                    '#' => mb.map.tiles[idx] = TileType::Wall,
-                    _ => println!("No idea what to do with [{}]", c),
+        stamp(mb, FORTRESS, placement);
+    }
+}
+
+pub fn apply_arena(mb: &mut MapBuilder) {
+    if mb.map.width < ARENA.1 + 2 || mb.map.height < ARENA.2 + 2 {
+        return;
+    }
+    let placement = Point::new(
+        (mb.amulet_start.x - ARENA.1 / 2).clamp(1, mb.map.width - ARENA.1 - 1),
+        (mb.amulet_start.y - ARENA.2 / 2).clamp(1, mb.map.height - ARENA.2 - 1),
+    );
+    let dimensions = Rect::with_size(placement.x, placement.y, ARENA.1, ARENA.2);
+    let points = dimensions.point_set();
+    if points.contains(&mb.player_start) {
+        return;
+    }
+    mb.monster_spawns.retain(|pt| !points.contains(pt));
+    mb.treasure_spawns.retain(|pt| !points.contains(pt));
+
+    stamp(mb, ARENA, placement);
+    mb.connect_regions();
+}
+
+fn stamp(mb: &mut MapBuilder, prefab: (&str, i32, i32), placement: Point) {
+    let string_vec: Vec<char> = prefab
+        .0
+        .chars()
+        .filter(|a| *a != '\r' && *a != '\n')
+        .collect();
+    let mut i = 0;
+    for ty in placement.y..placement.y + prefab.2 {
+        for tx in placement.x..placement.x + prefab.1 {
+            let idx = mb.map.idx(tx, ty);
+            let c = string_vec[i];
+            match c {
+                'M' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    mb.monster_spawns.push(Point::new(tx, ty));
+                }
+                'B' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    mb.amulet_start = Point::new(tx, ty);
+                }
+                '-' => mb.map.tiles[idx] = TileType::Floor,
+                '#' => {
+                    mb.map.tiles[idx] = TileType::Wall;
+                    mb.map.materials[idx] = Material::Bedrock;
                 }
-                i += 1;
+                _ => println!("No idea what to do with [{}]", c),
             }
+            i += 1;
         }
     }
 }
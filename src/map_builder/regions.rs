@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+const MIN_ROOM_SIZE: usize = 9;
+const MAX_ROOM_SPAN: i32 = 12;
+const ROOM_KINDS: [(RoomKind, i32); 5] = [
+    (RoomKind::Standard, 50),
+    (RoomKind::Lair, 15),
+    (RoomKind::Treasure, 15),
+    (RoomKind::Shrine, 10),
+    (RoomKind::Empty, 10),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoomKind {
+    Standard,
+    Lair,
+    Treasure,
+    Shrine,
+    Empty,
+}
+
+#[derive(Clone, Debug)]
+pub struct Room {
+    pub area: Rect,
+    pub tiles: Vec<Point>,
+    pub kind: RoomKind,
+}
+
+impl Room {
+    pub fn new(area: Rect) -> Self {
+        Self {
+            area,
+            tiles: Vec::new(),
+            kind: RoomKind::Standard,
+        }
+    }
+
+    fn from_tiles(mut tiles: Vec<Point>) -> Self {
+        tiles.sort_by_key(|pt| (pt.y, pt.x));
+        let x1 = tiles.iter().map(|pt| pt.x).min().unwrap();
+        let y1 = tiles.iter().map(|pt| pt.y).min().unwrap();
+        let x2 = tiles.iter().map(|pt| pt.x).max().unwrap();
+        let y2 = tiles.iter().map(|pt| pt.y).max().unwrap();
+        Self {
+            area: Rect::with_exact(x1, y1, x2 + 1, y2 + 1),
+            tiles,
+            kind: RoomKind::Standard,
+        }
+    }
+
+    pub fn center(&self) -> Point {
+        self.area.center()
+    }
+}
+
+impl MapBuilder {
+    pub fn detect_rooms(&mut self) {
+        if self.rooms.is_empty() {
+            self.rooms = self.open_areas();
+        }
+        let map = &self.map;
+        self.rooms.iter_mut().for_each(|room| {
+            if room.tiles.is_empty() {
+                room.tiles = room.area.point_set().into_iter().collect();
+                room.tiles.sort_by_key(|pt| (pt.y, pt.x));
+            }
+            room.tiles
+                .retain(|pt| map.in_bounds(*pt) && map.can_enter_tile(*pt));
+        });
+        self.rooms.retain(|room| !room.tiles.is_empty());
+    }
+
+    pub fn populate_rooms(&mut self, rng: &mut RandomNumberGenerator) {
+        let total_weight: i32 = ROOM_KINDS.iter().map(|(_, weight)| weight).sum();
+        let player_start = self.player_start;
+        for room in self.rooms.iter_mut() {
+            room.kind = if room.tiles.contains(&player_start) {
+                RoomKind::Empty
+            } else {
+                let mut roll = rng.range(0, total_weight);
+                ROOM_KINDS
+                    .iter()
+                    .find(|(_, weight)| {
+                        roll -= weight;
+                        roll < 0
+                    })
+                    .map(|(kind, _)| *kind)
+                    .unwrap_or(RoomKind::Standard)
+            };
+        }
+
+        let in_rooms: HashSet<Point> = self
+            .rooms
+            .iter()
+            .flat_map(|room| room.tiles.iter().copied())
+            .collect();
+        self.monster_spawns.retain(|pt| !in_rooms.contains(pt));
+
+        let min_distance = self.spawn_distance();
+        for i in 0..self.rooms.len() {
+            let room = &self.rooms[i];
+            let (monsters, treasures) = match room.kind {
+                RoomKind::Standard => (1 + room.tiles.len() / 60, 0),
+                RoomKind::Lair => (usize::min(8, 2 + room.tiles.len() / 8), 0),
+                RoomKind::Treasure => (1, rng.range(2, 4) as usize),
+                RoomKind::Shrine | RoomKind::Empty => (0, 0),
+            };
+            let mut free: Vec<Point> = room
+                .tiles
+                .iter()
+                .copied()
+                .filter(|pt| {
+                    *pt != player_start
+                        && *pt != self.amulet_start
+                        && !self.treasure_spawns.contains(pt)
+                })
+                .collect();
+            if room.kind == RoomKind::Shrine {
+                let center = room.center();
+                if let Some(pt) = free.iter().min_by(|a, b| {
+                    let da = DistanceAlg::Pythagoras.distance2d(center, **a);
+                    let db = DistanceAlg::Pythagoras.distance2d(center, **b);
+                    da.partial_cmp(&db).unwrap()
+                }) {
+                    self.treasure_spawns.push(*pt);
+                }
+                continue;
+            }
+            for _ in 0..treasures {
+                if let Some(idx) = rng.random_slice_index(&free) {
+                    self.treasure_spawns.push(free.remove(idx));
+                }
+            }
+            free.retain(|pt| DistanceAlg::Pythagoras.distance2d(player_start, *pt) > min_distance);
+            for _ in 0..monsters {
+                if let Some(idx) = rng.random_slice_index(&free) {
+                    self.monster_spawns.push(free.remove(idx));
+                }
+            }
+        }
+    }
+
+    fn open_areas(&self) -> Vec<Room> {
+        let is_open = |pt: Point| {
+            (-1..=1).all(|dy| {
+                (-1..=1).all(|dx| {
+                    let pt = pt + Point::new(dx, dy);
+                    self.map.in_bounds(pt) && self.map.can_enter_tile(pt)
+                })
+            })
+        };
+        let mut claimed = HashSet::new();
+        let mut rooms = Vec::new();
+        for idx in 0..self.map.tiles.len() {
+            let start = self.map.index_to_point2d(idx);
+            if claimed.contains(&start) || !is_open(start) {
+                continue;
+            }
+
+            let mut core = Vec::new();
+            let mut open = VecDeque::from(vec![start]);
+            while let Some(pt) = open.pop_front() {
+                if !is_open(pt) || !claimed.insert(pt) {
+                    continue;
+                }
+                core.push(pt);
+                open.extend(
+                    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .map(|(dx, dy)| pt + Point::new(*dx, *dy)),
+                );
+            }
+            if core.len() < MIN_ROOM_SIZE {
+                continue;
+            }
+
+            let mut tiles: HashSet<Point> = core.iter().copied().collect();
+            core.iter().for_each(|pt| {
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        tiles.insert(*pt + Point::new(dx, dy));
+                    }
+                }
+            });
+            let core: HashSet<Point> = core.into_iter().collect();
+            let tiles: Vec<Point> = tiles
+                .into_iter()
+                .filter(|pt| !claimed.contains(pt) || core.contains(pt))
+                .collect();
+            claimed.extend(tiles.iter().copied());
+
+            let mut sections: BTreeMap<(i32, i32), Vec<Point>> = BTreeMap::new();
+            tiles.into_iter().for_each(|pt| {
+                sections
+                    .entry((pt.y / MAX_ROOM_SPAN, pt.x / MAX_ROOM_SPAN))
+                    .or_default()
+                    .push(pt)
+            });
+            rooms.extend(
+                sections
+                    .into_values()
+                    .filter(|tiles| tiles.len() >= MIN_ROOM_SIZE)
+                    .map(Room::from_tiles),
+            );
+        }
+        rooms
+    }
+}
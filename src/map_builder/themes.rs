@@ -1,39 +1,172 @@
 use crate::prelude::*;
+use ron::de::from_str;
+use serde::Deserialize;
+use std::fs::read_to_string;
 
-pub struct DungeonTheme {}
+const THEMES_PATH: &str = "resources/themes.ron";
 
-impl DungeonTheme {
-    #[allow(clippy::new_ret_no_self)]
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self {})
-    }
+#[derive(Clone, Deserialize, Debug)]
+pub struct TileStyle {
+    pub glyphs: Vec<char>,
+    pub fg: (u8, u8, u8),
+    pub bg: (u8, u8, u8),
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub weight: i32,
+    pub architects: Option<Vec<ArchitectKind>>,
+    pub floor: TileStyle,
+    pub wall: TileStyle,
+    pub exit: TileStyle,
+    pub remembered: (u8, u8, u8),
+    pub ambient: Option<f32>,
+    pub torches: Option<f32>,
+    pub monsters: Option<Vec<String>>,
+    pub items: Option<Vec<String>>,
 }
 
-impl MapTheme for DungeonTheme {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-        match tile_type {
-            TileType::Floor => to_cp437('.'),
-            TileType::Wall => to_cp437('#'),
-            TileType::Exit => to_cp437('>'),
+impl Default for Theme {
+    fn default() -> Self {
+        let style = |glyph| TileStyle {
+            glyphs: vec![glyph],
+            fg: WHITE,
+            bg: BLACK,
+        };
+        Self {
+            name: "Dungeon".to_string(),
+            weight: 1,
+            architects: None,
+            floor: style('.'),
+            wall: style('#'),
+            exit: style('>'),
+            remembered: DARK_GRAY,
+            ambient: None,
+            torches: None,
+            monsters: None,
+            items: None,
         }
     }
 }
 
-pub struct ForestTheme {}
-
-impl ForestTheme {
-    #[allow(clippy::new_ret_no_self)]
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self {})
+impl Theme {
+    pub fn tile_to_render(&self, tile_type: TileType, pt: Point) -> (FontCharType, ColorPair) {
+        let style = match tile_type {
+            TileType::Floor => &self.floor,
+            TileType::Wall => &self.wall,
+            TileType::Exit => &self.exit,
+        };
+        let variant =
+            (pt.x as u32).wrapping_mul(73_856_093) ^ (pt.y as u32).wrapping_mul(19_349_663);
+        let glyph = style.glyphs[variant as usize % style.glyphs.len()];
+        (
+            to_cp437(glyph),
+            ColorPair::new(RGB::named(style.fg), RGB::named(style.bg)),
+        )
     }
 }
 
-impl MapTheme for ForestTheme {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-        match tile_type {
-            TileType::Floor => to_cp437(';'),
-            TileType::Wall => to_cp437('"'),
-            TileType::Exit => to_cp437('>'),
+#[derive(Clone, Deserialize, Debug)]
+pub struct Themes {
+    pub themes: Vec<Theme>,
+}
+
+impl Themes {
+    pub fn load(config: &GameConfig, templates: &Templates) -> Result<Self, Vec<String>> {
+        let source = read_to_string(THEMES_PATH)
+            .map_err(|e| vec![format!("{}: unable to read file ({})", THEMES_PATH, e)])?;
+        let themes: Themes = from_str(&source).map_err(|e| {
+            if e.position.line > 0 {
+                vec![format!("{}:{}: {}", THEMES_PATH, e.position, e.code)]
+            } else {
+                vec![format!("{}: {}", THEMES_PATH, e.code)]
+            }
+        })?;
+
+        let errors = themes.errors(config, templates);
+        if errors.is_empty() {
+            Ok(themes)
+        } else {
+            Err(errors
+                .iter()
+                .map(|error| format!("{}: {}", THEMES_PATH, error))
+                .collect())
         }
     }
+
+    fn errors(&self, config: &GameConfig, templates: &Templates) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.themes.iter().all(|theme| theme.weight <= 0) {
+            errors.push("at least one theme needs a positive weight".to_string());
+        }
+        self.themes.iter().for_each(|theme| {
+            [
+                ("floor", &theme.floor),
+                ("wall", &theme.wall),
+                ("exit", &theme.exit),
+            ]
+            .iter()
+            .filter(|(_, style)| style.glyphs.is_empty())
+            .for_each(|(tile, _)| {
+                errors.push(format!("theme {} has no {} glyphs", theme.name, tile))
+            });
+            theme
+                .monsters
+                .iter()
+                .chain(theme.items.iter())
+                .flatten()
+                .filter(|name| {
+                    templates.find(name).is_none()
+                        && !templates.groups.iter().flatten().any(|g| &&g.name == name)
+                })
+                .for_each(|name| {
+                    errors.push(format!("theme {} spawns unknown {}", theme.name, name))
+                });
+        });
+        config
+            .depths
+            .iter()
+            .filter_map(|(depth, level)| level.theme.as_ref().map(|theme| (depth, theme)))
+            .filter(|(_, name)| self.find(name).is_none())
+            .for_each(|(depth, name)| {
+                errors.push(format!("depth {} uses unknown theme {}", depth, name))
+            });
+        errors
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    pub fn pick(
+        &self,
+        level: &LevelConfig,
+        architect: Option<ArchitectKind>,
+        rng: &mut RandomNumberGenerator,
+    ) -> Theme {
+        if let Some(theme) = level.theme.as_deref().and_then(|name| self.find(name)) {
+            return theme.clone();
+        }
+        if let Some(theme) = self.themes.iter().find(|theme| {
+            theme
+                .architects
+                .iter()
+                .flatten()
+                .any(|kind| Some(*kind) == architect)
+        }) {
+            return theme.clone();
+        }
+
+        let total_weight: i32 = self.themes.iter().map(|theme| theme.weight.max(0)).sum();
+        let mut roll = rng.range(0, total_weight);
+        self.themes
+            .iter()
+            .find(|theme| {
+                roll -= theme.weight.max(0);
+                roll < 0
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
 }
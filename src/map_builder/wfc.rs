@@ -0,0 +1,305 @@
+use super::{MapArchitect, RoomsArchitect};
+use crate::prelude::*;
+use std::collections::HashSet;
+
+const CHUNK_SIZE: i32 = 4;
+const MAX_COLLAPSE_ATTEMPTS: usize = 20;
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+const SAMPLE: &str = "
+################################
+#......#########.......#########
+#......#########.......#########
+#......................#########
+#......#########.......#########
+###.############.......###...###
+###.#############.#######.....##
+###.#############.#######.....##
+###...............##.......#..##
+#####.###########.##.####.....##
+#####.###########.##.####.....##
+##.......########.##.#####...###
+##.......########....#####.#####
+##.......##########.######.#####
+##...............##.######.#####
+##.......#######.##........#####
+##.......#######.####.##########
+#######.########.####.##########
+#######..........####.......####
+################################
+";
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Chunk(Vec<bool>);
+
+impl Chunk {
+    fn floor(&self, x: i32, y: i32) -> bool {
+        self.0[(y * CHUNK_SIZE + x) as usize]
+    }
+
+    fn rotated(&self) -> Chunk {
+        Chunk(
+            (0..CHUNK_SIZE * CHUNK_SIZE)
+                .map(|i| self.floor(i / CHUNK_SIZE, CHUNK_SIZE - 1 - i % CHUNK_SIZE))
+                .collect(),
+        )
+    }
+
+    fn flipped(&self) -> Chunk {
+        Chunk(
+            (0..CHUNK_SIZE * CHUNK_SIZE)
+                .map(|i| self.floor(CHUNK_SIZE - 1 - i % CHUNK_SIZE, i / CHUNK_SIZE))
+                .collect(),
+        )
+    }
+
+    fn edge(&self, direction: usize) -> Vec<bool> {
+        (0..CHUNK_SIZE)
+            .map(|i| match direction {
+                0 => self.floor(i, 0),
+                1 => self.floor(CHUNK_SIZE - 1, i),
+                2 => self.floor(i, CHUNK_SIZE - 1),
+                _ => self.floor(0, i),
+            })
+            .collect()
+    }
+}
+
+fn sample_rows(sample: &str) -> Vec<Vec<bool>> {
+    sample
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().map(|c| c != '#').collect())
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Sample(Vec<Vec<bool>>);
+
+impl Sample {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let rows = sample_rows(source);
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if rows.len() < CHUNK_SIZE as usize || width < CHUNK_SIZE as usize {
+            return Err(format!(
+                "sample must be at least {}x{}",
+                CHUNK_SIZE, CHUNK_SIZE
+            ));
+        }
+        if !rows.iter().flatten().any(|floor| *floor) {
+            return Err("sample has no floor".to_string());
+        }
+        Ok(Sample(rows))
+    }
+}
+
+pub struct WaveFunctionCollapseArchitect {
+    chunks: Vec<Chunk>,
+    compatible: Vec<Vec<Vec<bool>>>,
+}
+
+impl WaveFunctionCollapseArchitect {
+    pub fn new(sample: Option<&Sample>) -> Self {
+        let rows = sample.map_or_else(|| sample_rows(SAMPLE), |sample| sample.0.clone());
+        let floor = |x: i32, y: i32| {
+            rows.get(y as usize)
+                .and_then(|row| row.get(x as usize))
+                .copied()
+                .unwrap_or(false)
+        };
+        let height = rows.len() as i32;
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+
+        let mut chunks = Vec::new();
+        let mut seen = HashSet::new();
+        for y in 0..=height - CHUNK_SIZE {
+            for x in 0..=width - CHUNK_SIZE {
+                let mut chunk = Chunk(
+                    (0..CHUNK_SIZE * CHUNK_SIZE)
+                        .map(|i| floor(x + i % CHUNK_SIZE, y + i / CHUNK_SIZE))
+                        .collect(),
+                );
+                for _ in 0..4 {
+                    for variant in [chunk.clone(), chunk.flipped()] {
+                        if seen.insert(variant.clone()) {
+                            chunks.push(variant);
+                        }
+                    }
+                    chunk = chunk.rotated();
+                }
+            }
+        }
+
+        let compatible = (0..DIRECTIONS.len())
+            .map(|direction| {
+                let opposite = (direction + 2) % 4;
+                chunks
+                    .iter()
+                    .map(|a| {
+                        chunks
+                            .iter()
+                            .map(|b| a.edge(direction) == b.edge(opposite))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { chunks, compatible }
+    }
+
+    fn collapse(
+        &self,
+        rng: &mut RandomNumberGenerator,
+        columns: i32,
+        rows: i32,
+    ) -> Option<Vec<usize>> {
+        let mut cells: Vec<Vec<usize>> =
+            vec![(0..self.chunks.len()).collect(); (columns * rows) as usize];
+        if cells.iter().any(|candidates| candidates.is_empty()) {
+            return None;
+        }
+
+        loop {
+            let lowest = cells
+                .iter()
+                .map(|candidates| candidates.len())
+                .filter(|len| *len > 1)
+                .min();
+            let lowest = match lowest {
+                Some(lowest) => lowest,
+                None => break,
+            };
+            let undecided: Vec<usize> = (0..cells.len())
+                .filter(|idx| cells[*idx].len() == lowest)
+                .collect();
+            let cell = *rng.random_slice_entry(&undecided).unwrap();
+            let choice = *rng.random_slice_entry(&cells[cell]).unwrap();
+            cells[cell] = vec![choice];
+
+            let mut open = vec![cell];
+            while let Some(cell) = open.pop() {
+                let (x, y) = (cell as i32 % columns, cell as i32 / columns);
+                for (direction, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx >= columns || ny < 0 || ny >= rows {
+                        continue;
+                    }
+                    let neighbour = (ny * columns + nx) as usize;
+                    let before = cells[neighbour].len();
+                    let supported: Vec<usize> = cells[neighbour]
+                        .iter()
+                        .copied()
+                        .filter(|candidate| {
+                            cells[cell]
+                                .iter()
+                                .any(|chunk| self.compatible[direction][*chunk][*candidate])
+                        })
+                        .collect();
+                    if supported.is_empty() {
+                        return None;
+                    }
+                    if supported.len() < before {
+                        cells[neighbour] = supported;
+                        open.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        Some(cells.iter().map(|candidates| candidates[0]).collect())
+    }
+}
+
+impl MapArchitect for WaveFunctionCollapseArchitect {
+    fn new_map_builder(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> MapBuilder {
+        let (columns, rows) = ((width - 2) / CHUNK_SIZE, (height - 2) / CHUNK_SIZE);
+        let collapsed =
+            match (0..MAX_COLLAPSE_ATTEMPTS).find_map(|_| self.collapse(rng, columns, rows)) {
+                Some(collapsed) => collapsed,
+                None => return RoomsArchitect {}.new_map_builder(rng, width, height),
+            };
+
+        let mut mb = MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            treasure_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Theme::default(),
+        };
+        mb.fill(TileType::Wall);
+        collapsed.iter().enumerate().for_each(|(cell, chunk)| {
+            let origin = Point::new(
+                1 + (cell as i32 % columns) * CHUNK_SIZE,
+                1 + (cell as i32 / columns) * CHUNK_SIZE,
+            );
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    if self.chunks[*chunk].floor(x, y) {
+                        let idx = mb.map.idx(origin.x + x, origin.y + y);
+                        mb.map.tiles[idx] = TileType::Floor;
+                    }
+                }
+            }
+        });
+
+        let center = Point::new(width / 2, height / 2);
+        let start = mb
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == TileType::Floor)
+            .map(|(idx, _)| mb.map.index_to_point2d(idx))
+            .min_by(|a, b| {
+                let da = DistanceAlg::Pythagoras.distance2d(center, *a);
+                let db = DistanceAlg::Pythagoras.distance2d(center, *b);
+                da.partial_cmp(&db).unwrap()
+            })
+            .unwrap_or(center);
+        let start_idx = mb.map.point2d_to_index(start);
+        mb.map.tiles[start_idx] = TileType::Floor;
+
+        mb.player_start = start;
+        mb.monster_spawns = mb.spawn_monsters(&start, rng);
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_small_or_solid_samples() {
+        assert!(Sample::parse("##\n#.\n").is_err());
+        assert!(Sample::parse("####\n####\n####\n####\n").is_err());
+        assert!(Sample::parse(SAMPLE).is_ok());
+    }
+
+    #[test]
+    fn unusable_sample_falls_back_to_another_architect() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let sample = Sample(sample_rows("##\n#.\n"));
+        let mb = WaveFunctionCollapseArchitect::new(Some(&sample)).new_map_builder(
+            &mut rng,
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+        );
+        let floor = mb
+            .map
+            .tiles
+            .iter()
+            .filter(|tile| **tile == TileType::Floor)
+            .count();
+        assert!(floor > 1);
+    }
+}
@@ -5,22 +5,36 @@ pub struct Camera {
     pub right_x: i32,
     pub top_y: i32,
     pub bottom_y: i32,
+    map_width: i32,
+    map_height: i32,
 }
 
 impl Camera {
-    pub fn new(player_position: Point) -> Self {
-        Camera {
-            left_x: player_position.x - DISPLAY_WIDTH / 2,
-            right_x: player_position.x + DISPLAY_WIDTH / 2,
-            top_y: player_position.y - DISPLAY_HEIGHT / 2,
-            bottom_y: player_position.y + DISPLAY_HEIGHT / 2,
-        }
+    pub fn new(player_position: Point, map: &Map) -> Self {
+        let mut camera = Camera {
+            left_x: 0,
+            right_x: 0,
+            top_y: 0,
+            bottom_y: 0,
+            map_width: map.width,
+            map_height: map.height,
+        };
+        camera.on_player_move(player_position);
+        camera
     }
 
     pub fn on_player_move(&mut self, player_position: Point) {
-        self.left_x = player_position.x - DISPLAY_WIDTH / 2;
-        self.right_x = player_position.x + DISPLAY_WIDTH / 2;
-        self.top_y = player_position.y - DISPLAY_HEIGHT / 2;
-        self.bottom_y = player_position.y + DISPLAY_HEIGHT / 2;
+        self.left_x = Camera::clamp(player_position.x, DISPLAY_WIDTH, self.map_width);
+        self.right_x = self.left_x + DISPLAY_WIDTH;
+        self.top_y = Camera::clamp(player_position.y, DISPLAY_HEIGHT, self.map_height);
+        self.bottom_y = self.top_y + DISPLAY_HEIGHT;
+    }
+
+    fn clamp(center: i32, display: i32, map: i32) -> i32 {
+        if map <= display {
+            (map - display) / 2
+        } else {
+            (center - display / 2).clamp(0, map - display)
+        }
     }
 }
@@ -0,0 +1,17 @@
+use std::collections::VecDeque;
+
+const MAX_ENTRIES: usize = 5;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CombatLog {
+    pub entries: VecDeque<String>,
+}
+
+impl CombatLog {
+    pub fn add(&mut self, entry: String) {
+        self.entries.push_back(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+}
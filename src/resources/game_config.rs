@@ -0,0 +1,250 @@
+use crate::prelude::*;
+use ron::de::from_str;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+
+const CONFIG_PATH: &str = "resources/game.ron";
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct GameConfig {
+    pub levels: usize,
+    pub amulet_level: usize,
+    pub score: ScoreConfig,
+    pub victory: Vec<VictoryCondition>,
+    pub depths: HashMap<usize, LevelConfig>,
+    pub difficulty: DifficultyLevel,
+    pub difficulties: HashMap<DifficultyLevel, Difficulty>,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum DifficultyLevel {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Difficulty {
+    pub monster_count: f32,
+    pub monster_hp: f32,
+    pub monster_damage: f32,
+    pub item_frequency: f32,
+    pub healing: f32,
+    pub player_hp: i32,
+    pub player_damage: i32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            monster_count: 1.0,
+            monster_hp: 1.0,
+            monster_damage: 1.0,
+            item_frequency: 1.0,
+            healing: 1.0,
+            player_hp: 10,
+            player_damage: 1,
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn scale(amount: i32, factor: f32) -> i32 {
+        i32::max(1, (amount as f32 * factor).round() as i32)
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct ScoreConfig {
+    pub level_bonus: u32,
+    pub kill_bonus: u32,
+    pub victory_bonus: u32,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub enum VictoryCondition {
+    Amulet,
+    Escape,
+    Score(u32),
+}
+
+pub const MIN_MAP_WIDTH: i32 = 16;
+pub const MIN_MAP_HEIGHT: i32 = 12;
+
+#[derive(Clone, Default, Deserialize, Debug)]
+pub struct LevelConfig {
+    pub architect: Option<ArchitectKind>,
+    pub theme: Option<String>,
+    pub map: Option<String>,
+    pub size: Option<(i32, i32)>,
+    pub sample: Option<String>,
+    #[serde(skip)]
+    pub loaded_sample: Option<Sample>,
+    pub corridor_width: Option<i32>,
+    pub braid: Option<f32>,
+    pub steps: Option<Vec<BuildStep>>,
+}
+
+impl GameConfig {
+    pub fn load() -> Result<Self, Vec<String>> {
+        let source = read_to_string(CONFIG_PATH)
+            .map_err(|e| vec![format!("{}: unable to read file ({})", CONFIG_PATH, e)])?;
+        let mut config: GameConfig = from_str(&source).map_err(|e| {
+            if e.position.line > 0 {
+                vec![format!("{}:{}: {}", CONFIG_PATH, e.position, e.code)]
+            } else {
+                vec![format!("{}: {}", CONFIG_PATH, e.code)]
+            }
+        })?;
+
+        let mut errors = config.errors();
+        errors.extend(config.load_samples());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors
+                .iter()
+                .map(|error| format!("{}: {}", CONFIG_PATH, error))
+                .collect())
+        }
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.levels == 0 {
+            errors.push("levels must be at least 1".to_string());
+        }
+        if self.amulet_level >= self.levels {
+            errors.push(format!(
+                "amulet_level {} is deeper than the {} configured levels",
+                self.amulet_level, self.levels
+            ));
+        }
+        if self.victory.is_empty() {
+            errors.push("at least one victory condition is required".to_string());
+        }
+        if !self.difficulties.contains_key(&self.difficulty) {
+            errors.push(format!("difficulty {:?} has no preset", self.difficulty));
+        }
+        self.depths
+            .keys()
+            .filter(|depth| **depth >= self.levels)
+            .for_each(|depth| {
+                errors.push(format!(
+                    "depth {} is configured but there are only {} levels",
+                    depth, self.levels
+                ))
+            });
+        self.depths
+            .iter()
+            .filter_map(|(depth, level)| level.size.map(|size| (depth, size)))
+            .filter(|(_, (width, height))| *width < MIN_MAP_WIDTH || *height < MIN_MAP_HEIGHT)
+            .for_each(|(depth, (width, height))| {
+                errors.push(format!(
+                    "depth {} size {}x{} is smaller than {}x{}",
+                    depth, width, height, MIN_MAP_WIDTH, MIN_MAP_HEIGHT
+                ))
+            });
+        self.depths
+            .iter()
+            .filter_map(|(depth, level)| level.map.as_ref().map(|path| (depth, path)))
+            .filter_map(|(depth, path)| {
+                let error = std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| MapBuilder::from_ascii(&source))
+                    .err()?;
+                Some(format!("depth {} map {}: {}", depth, path, error))
+            })
+            .for_each(|error| errors.push(error));
+        self.depths
+            .iter()
+            .filter(|(_, level)| level.corridor_width.is_some_and(|width| width < 1))
+            .for_each(|(depth, _)| {
+                errors.push(format!("depth {} corridor_width must be at least 1", depth))
+            });
+        self.depths
+            .iter()
+            .filter(|(_, level)| {
+                level
+                    .braid
+                    .is_some_and(|braid| !(0.0..=1.0).contains(&braid))
+            })
+            .for_each(|(depth, _)| {
+                errors.push(format!("depth {} braid must be between 0.0 and 1.0", depth))
+            });
+        self.depths
+            .iter()
+            .filter_map(|(depth, level)| level.steps.as_ref().map(|steps| (depth, level, steps)))
+            .for_each(|(depth, level, steps)| {
+                match steps.first() {
+                    Some(BuildStep::Base(_)) | Some(BuildStep::Regions(_)) => {}
+                    _ => errors.push(format!(
+                        "depth {} steps must start with Base or Regions",
+                        depth
+                    )),
+                }
+                let (width, _) = level.size.unwrap_or((SCREEN_WIDTH, SCREEN_HEIGHT));
+                steps.iter().for_each(|step| match step {
+                    BuildStep::Regions(kinds) if kinds.is_empty() => {
+                        errors.push(format!("depth {} Regions needs an architect", depth))
+                    }
+                    BuildStep::Regions(kinds) if width / (kinds.len() as i32) < MIN_MAP_WIDTH => {
+                        errors.push(format!(
+                            "depth {} is too narrow for {} regions of at least {} tiles",
+                            depth,
+                            kinds.len(),
+                            MIN_MAP_WIDTH
+                        ))
+                    }
+                    BuildStep::ErodeWalls(chance) if !(0.0..=1.0).contains(chance) => errors.push(
+                        format!("depth {} ErodeWalls must be between 0.0 and 1.0", depth),
+                    ),
+                    _ => {}
+                });
+            });
+        errors
+    }
+
+    fn load_samples(&mut self) -> Vec<String> {
+        self.depths
+            .iter_mut()
+            .filter_map(|(depth, level)| {
+                let path = level.sample.clone()?;
+                match std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| Sample::parse(&source))
+                {
+                    Ok(sample) => {
+                        level.loaded_sample = Some(sample);
+                        None
+                    }
+                    Err(error) => Some(format!("depth {} sample {}: {}", depth, path, error)),
+                }
+            })
+            .collect()
+    }
+
+    pub fn level(&self, depth: usize) -> LevelConfig {
+        self.depths.get(&depth).cloned().unwrap_or_default()
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulties
+            .get(&self.difficulty)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn is_escape(&self) -> bool {
+        self.victory.contains(&VictoryCondition::Escape)
+    }
+
+    pub fn score_target(&self) -> Option<u32> {
+        self.victory.iter().find_map(|v| match v {
+            VictoryCondition::Score(target) => Some(*target),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,5 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyModifiers {
+    pub shift: bool,
+    pub control: bool,
+}
@@ -0,0 +1,9 @@
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseClick(pub bool);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Inspect {
+    pub target: Option<Entity>,
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+
+const DARK: f32 = 0.3;
+const MIN_BRIGHTNESS: f32 = 0.2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightMap {
+    pub ambient: f32,
+    pub width: i32,
+    pub levels: Vec<f32>,
+    pub colors: Vec<RGB>,
+}
+
+impl LightMap {
+    pub fn new(map: &Map, ambient: f32) -> Self {
+        let tiles = (map.width * map.height) as usize;
+        Self {
+            ambient,
+            width: map.width,
+            levels: vec![ambient; tiles],
+            colors: vec![RGB::named(WHITE); tiles],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        let ambient = self.ambient;
+        self.levels.iter_mut().for_each(|level| *level = ambient);
+        self.colors
+            .iter_mut()
+            .for_each(|color| *color = RGB::named(WHITE));
+    }
+
+    pub fn add_light(&mut self, map: &Map, pos: Point, light: &LightSource) {
+        let lit: Vec<(Point, usize)> = field_of_view_set(pos, light.radius, map)
+            .into_iter()
+            .filter_map(|pt| self.idx(pt).map(|idx| (pt, idx)))
+            .collect();
+        lit.into_iter().for_each(|(pt, idx)| {
+            let distance = DistanceAlg::Pythagoras.distance2d(pos, pt);
+            let intensity = f32::max(0.0, 1.0 - distance / (light.radius + 1) as f32);
+            let level = f32::min(1.0, self.levels[idx] + intensity);
+            if level > 0.0 {
+                self.colors[idx] = self.colors[idx].lerp(light.color, intensity / level / 2.0);
+            }
+            self.levels[idx] = level;
+        });
+    }
+
+    pub fn is_dark(&self, pt: Point) -> bool {
+        self.idx(pt).is_none_or(|idx| self.levels[idx] < DARK)
+    }
+
+    pub fn can_see(&self, viewer: Point, target: Point) -> bool {
+        !self.is_dark(target) || DistanceAlg::Pythagoras.distance2d(viewer, target) < 1.5
+    }
+
+    pub fn tint(&self, pt: Point, color: RGBA) -> RGBA {
+        match self.idx(pt) {
+            Some(idx) => {
+                let light = self.colors[idx] * f32::max(MIN_BRIGHTNESS, self.levels[idx]);
+                RGBA::from_f32(
+                    color.r * light.r,
+                    color.g * light.g,
+                    color.b * light.b,
+                    color.a,
+                )
+            }
+            None => color,
+        }
+    }
+
+    fn idx(&self, pt: Point) -> Option<usize> {
+        let height = self.levels.len() as i32 / self.width;
+        if pt.x < 0 || pt.x >= self.width || pt.y < 0 || pt.y >= height {
+            None
+        } else {
+            Some((pt.y * self.width + pt.x) as usize)
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use crate::prelude::*;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum TileType {
@@ -8,38 +7,95 @@ pub enum TileType {
     Exit,
 }
 
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * SCREEN_WIDTH) + x) as usize
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Material {
+    Soil,
+    Stone,
+    Granite,
+    Bedrock,
+}
+
+impl Material {
+    pub fn hardness(&self) -> Option<i32> {
+        match self {
+            Material::Soil => Some(2),
+            Material::Stone => Some(3),
+            Material::Granite => Some(6),
+            Material::Bedrock => None,
+        }
+    }
+
+    pub fn tint(&self) -> (u8, u8, u8) {
+        match self {
+            Material::Soil => (210, 170, 120),
+            Material::Stone => WHITE,
+            Material::Granite => (170, 170, 210),
+            Material::Bedrock => (110, 110, 110),
+        }
+    }
 }
 
 pub struct Map {
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
+    pub materials: Vec<Material>,
+    pub dig_progress: Vec<i32>,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            materials: vec![Material::Stone; num_tiles],
+            dig_progress: vec![0; num_tiles],
         }
     }
 
+    pub fn idx(&self, x: i32, y: i32) -> usize {
+        ((y * self.width) + x) as usize
+    }
+
     pub fn in_bounds(&self, point: Point) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
     }
 
     pub fn can_enter_tile(&self, point: Point) -> bool {
         self.in_bounds(point)
-            && (self.tiles[map_idx(point.x, point.y)] == TileType::Floor
-                || self.tiles[map_idx(point.x, point.y)] == TileType::Exit)
+            && (self.tiles[self.idx(point.x, point.y)] == TileType::Floor
+                || self.tiles[self.idx(point.x, point.y)] == TileType::Exit)
+    }
+
+    pub fn can_dig(&self, point: Point) -> bool {
+        self.try_idx(point).is_some_and(|idx| {
+            self.tiles[idx] == TileType::Wall && self.materials[idx].hardness().is_some()
+        })
+    }
+
+    pub fn dig(&mut self, point: Point) -> bool {
+        if !self.can_dig(point) {
+            return false;
+        }
+        let idx = self.point2d_to_index(point);
+        self.dig_progress[idx] += 1;
+        if self.dig_progress[idx] < self.materials[idx].hardness().unwrap() {
+            return false;
+        }
+        self.tiles[idx] = TileType::Floor;
+        self.dig_progress[idx] = 0;
+        true
     }
 
     pub fn try_idx(&self, point: Point) -> Option<usize> {
         if !self.in_bounds(point) {
             None
         } else {
-            Some(map_idx(point.x, point.y))
+            Some(self.idx(point.x, point.y))
         }
     }
 
@@ -56,7 +112,7 @@ impl Map {
 
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, point: Point) -> bool {
@@ -90,6 +146,6 @@ impl BaseMap for Map {
     }
 
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        self.tiles[idx] != TileType::Floor
     }
 }
@@ -1,6 +1,15 @@
 mod camera;
+mod combat_log;
+mod game_config;
+mod input;
+mod inspect;
+mod light_map;
 mod map;
+mod template_status;
 mod timer;
 mod turn_state;
 
-pub use {camera::*, map::*, timer::*, turn_state::*};
+pub use {
+    camera::*, combat_log::*, game_config::*, input::*, inspect::*, light_map::*, map::*,
+    template_status::*, timer::*, turn_state::*,
+};
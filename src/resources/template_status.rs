@@ -0,0 +1,5 @@
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TemplateStatus {
+    pub errors: Vec<String>,
+    pub reloaded_for: f32,
+}
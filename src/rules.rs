@@ -0,0 +1,280 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+const HIT_TARGET: i32 = 6;
+const SNEAK_ATTACK_MULTIPLIER: i32 = 2;
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Cold,
+    Poison,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(untagged)]
+enum DiceSource {
+    Fixed(i32),
+    Roll(String),
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(try_from = "DiceSource")]
+pub struct Dice {
+    pub count: i32,
+    pub sides: i32,
+    pub bonus: i32,
+}
+
+impl TryFrom<DiceSource> for Dice {
+    type Error = String;
+
+    fn try_from(source: DiceSource) -> Result<Self, Self::Error> {
+        match source {
+            DiceSource::Fixed(bonus) => Ok(Dice::fixed(bonus)),
+            DiceSource::Roll(text) => Dice::parse(&text),
+        }
+    }
+}
+
+impl Dice {
+    pub fn fixed(bonus: i32) -> Self {
+        Self {
+            count: 0,
+            sides: 0,
+            bonus,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid dice {:?}, expected something like \"1d6+1\"", text);
+        let text = text.trim();
+        let number = |s: &str| s.trim().parse::<i32>().map_err(|_| invalid());
+        let (dice, bonus) = match text.rfind(['+', '-']) {
+            Some(at) if at > 0 => (&text[..at], number(&text[at..])?),
+            _ => (text, 0),
+        };
+        let dice = match dice.split_once('d') {
+            Some((count, sides)) => Dice {
+                count: if count.is_empty() { 1 } else { number(count)? },
+                sides: number(sides)?,
+                bonus,
+            },
+            None => Dice::fixed(number(dice)? + bonus),
+        };
+        if dice.count < 0 || (dice.count > 0 && dice.sides < 1) {
+            return Err(invalid());
+        }
+        Ok(dice)
+    }
+
+    pub fn plus(&self, bonus: i32) -> Self {
+        Self {
+            bonus: self.bonus + bonus,
+            ..*self
+        }
+    }
+
+    pub fn scaled(&self, factor: f32) -> Self {
+        if self.count == 0 && self.bonus == 0 {
+            return *self;
+        }
+        if self.count == 0 {
+            return Dice::fixed(i32::max(1, (self.bonus as f32 * factor).round() as i32));
+        }
+        Self {
+            count: self.count,
+            sides: i32::max(1, (self.sides as f32 * factor).round() as i32),
+            bonus: (self.bonus as f32 * factor).round() as i32,
+        }
+    }
+
+    fn roll_dice(&self, roll: &mut dyn FnMut(i32) -> i32) -> i32 {
+        (0..self.count).map(|_| roll(self.sides)).sum()
+    }
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.count, self.bonus) {
+            (0, bonus) => write!(f, "{}", bonus),
+            (count, 0) => write!(f, "{}d{}", count, self.sides),
+            (count, bonus) => write!(f, "{}d{}{:+}", count, self.sides, bonus),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attack {
+    pub to_hit: i32,
+    pub damage: Vec<Dice>,
+    pub kind: DamageType,
+    pub sneak: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Defence {
+    pub defence: i32,
+    pub resistances: HashMap<DamageType, i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttackOutcome {
+    Miss,
+    Hit(i32),
+    Critical(i32),
+}
+
+pub fn resist(damage: i32, resistance: i32) -> i32 {
+    i32::max(0, damage * (100 - resistance.min(100)) / 100)
+}
+
+pub fn resolve_attack(
+    attack: &Attack,
+    defence: &Defence,
+    roll: &mut dyn FnMut(i32) -> i32,
+) -> AttackOutcome {
+    let natural = roll(20);
+    let critical = natural == 20;
+    let hits = attack.sneak
+        || critical
+        || (natural > 1 && natural + attack.to_hit >= HIT_TARGET + defence.defence);
+    if !hits {
+        return AttackOutcome::Miss;
+    }
+
+    let mut roll_damage = || -> i32 {
+        attack
+            .damage
+            .iter()
+            .map(|dice| dice.roll_dice(roll) + dice.bonus)
+            .sum()
+    };
+    let mut damage = roll_damage();
+    if critical {
+        damage += roll_damage();
+    }
+    if attack.sneak {
+        damage *= SNEAK_ATTACK_MULTIPLIER;
+    }
+    let resistance = defence.resistances.get(&attack.kind).copied().unwrap_or(0);
+    let damage = resist(i32::max(0, damage), resistance);
+
+    if critical {
+        AttackOutcome::Critical(damage)
+    } else {
+        AttackOutcome::Hit(damage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripted(rolls: &[i32]) -> impl FnMut(i32) -> i32 + '_ {
+        let mut rolls = rolls.iter();
+        move |sides| {
+            let roll = *rolls.next().expect("ran out of scripted rolls");
+            assert!(
+                roll >= 1 && roll <= sides,
+                "rolled {} on a d{}",
+                roll,
+                sides
+            );
+            roll
+        }
+    }
+
+    fn attack(to_hit: i32, damage: &str) -> Attack {
+        Attack {
+            to_hit,
+            damage: vec![Dice::parse(damage).unwrap()],
+            kind: DamageType::Fire,
+            sneak: false,
+        }
+    }
+
+    fn defence(defence: i32) -> Defence {
+        Defence {
+            defence,
+            resistances: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn natural_one_always_misses() {
+        let outcome = resolve_attack(&attack(100, "1d6"), &defence(0), &mut scripted(&[1]));
+        assert_eq!(outcome, AttackOutcome::Miss);
+    }
+
+    #[test]
+    fn natural_twenty_is_a_critical() {
+        let outcome = resolve_attack(
+            &attack(0, "1d6+1"),
+            &defence(100),
+            &mut scripted(&[20, 4, 3]),
+        );
+        assert_eq!(outcome, AttackOutcome::Critical(9));
+    }
+
+    #[test]
+    fn critical_doubles_fixed_damage() {
+        let outcome = resolve_attack(&attack(0, "2"), &defence(0), &mut scripted(&[20]));
+        assert_eq!(outcome, AttackOutcome::Critical(4));
+    }
+
+    #[test]
+    fn scaling_keeps_zero_damage_at_zero() {
+        assert_eq!(Dice::fixed(0).scaled(0.5), Dice::fixed(0));
+        assert_eq!(Dice::fixed(1).scaled(0.1), Dice::fixed(1));
+        assert_eq!(Dice::fixed(4).scaled(1.5), Dice::fixed(6));
+    }
+
+    #[test]
+    fn hit_threshold_rises_with_defence() {
+        // to_hit 2 against defence 1
+        let target = HIT_TARGET + 1 - 2;
+        let miss = resolve_attack(&attack(2, "1d4"), &defence(1), &mut scripted(&[target - 1]));
+        assert_eq!(miss, AttackOutcome::Miss);
+        let hit = resolve_attack(&attack(2, "1d4"), &defence(1), &mut scripted(&[target, 3]));
+        assert_eq!(hit, AttackOutcome::Hit(3));
+    }
+
+    #[test]
+    fn sneak_attack_always_hits_for_extra_damage() {
+        let mut sneak = attack(0, "1d6");
+        sneak.sneak = true;
+        let outcome = resolve_attack(&sneak, &defence(100), &mut scripted(&[1, 3]));
+        assert_eq!(outcome, AttackOutcome::Hit(3 * SNEAK_ATTACK_MULTIPLIER));
+    }
+
+    #[test]
+    fn resistances_scale_damage() {
+        let mut immune = defence(0);
+        immune.resistances.insert(DamageType::Fire, 100);
+        let outcome = resolve_attack(&attack(0, "4"), &immune, &mut scripted(&[10]));
+        assert_eq!(outcome, AttackOutcome::Hit(0));
+
+        let mut vulnerable = defence(0);
+        vulnerable.resistances.insert(DamageType::Fire, -50);
+        let outcome = resolve_attack(&attack(0, "4"), &vulnerable, &mut scripted(&[10]));
+        assert_eq!(outcome, AttackOutcome::Hit(6));
+    }
+
+    #[test]
+    fn parses_dice() {
+        let dice = |count, sides, bonus| Dice {
+            count,
+            sides,
+            bonus,
+        };
+        assert_eq!(Dice::parse("1d6+1"), Ok(dice(1, 6, 1)));
+        assert_eq!(Dice::parse("d6"), Ok(dice(1, 6, 0)));
+        assert_eq!(Dice::parse("-3"), Ok(Dice::fixed(-3)));
+        assert!(Dice::parse("2d0").is_err());
+        assert!(Dice::parse("1d6+1+2").is_err());
+        assert!(Dice::parse("").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use super::spawn_table::{Guarantee, SpawnBudget};
+use super::template::*;
+use super::validation::*;
+use crate::rules::{DamageType, Dice};
+use serde::{Deserialize, Deserializer};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Default, Deserialize, Debug)]
+#[serde(rename = "Template")]
+pub struct TemplateSource {
+    pub name: String,
+    #[serde(default, deserialize_with = "present")]
+    pub inherits: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub entity_type: Option<EntityType>,
+    #[serde(default, deserialize_with = "present")]
+    pub levels: Option<HashSet<usize>>,
+    #[serde(default, deserialize_with = "present")]
+    pub frequency: Option<i32>,
+    pub ramp: Option<f32>,
+    #[serde(default, deserialize_with = "present")]
+    pub glyph: Option<char>,
+    pub color: Option<(u8, u8, u8)>,
+    pub background: Option<(u8, u8, u8)>,
+    pub render_order: Option<i32>,
+    pub fov_radius: Option<i32>,
+    pub perception: Option<i32>,
+    pub asleep: Option<bool>,
+    pub description: Option<String>,
+    pub effects: Option<Vec<Effect>>,
+    pub hp: Option<i32>,
+    pub base_damage: Option<Dice>,
+    pub damage_type: Option<DamageType>,
+    pub attack: Option<i32>,
+    pub defence: Option<i32>,
+    pub resistances: Option<HashMap<DamageType, i32>>,
+    pub faction: Option<String>,
+    pub durability: Option<i32>,
+    pub behaviours: Option<Vec<Behaviour>>,
+    pub abilities: Option<Vec<AbilityTemplate>>,
+    pub phases: Option<Vec<PhaseTemplate>>,
+    // Pre-typed forms, still read so older template files keep loading.
+    pub provides: Option<Vec<(String, i32)>>,
+    pub ai: Option<Vec<String>>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename = "Templates")]
+pub struct TemplatesSource {
+    pub entities: Vec<TemplateSource>,
+    pub bases: Option<Vec<TemplateSource>>,
+    pub groups: Option<Vec<GroupTemplate>>,
+    pub affixes: Option<Vec<Affix>>,
+    pub budgets: Option<Vec<SpawnBudget>>,
+    pub guarantees: Option<Vec<Guarantee>>,
+    pub factions: Option<Vec<FactionTemplate>>,
+}
+
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl TemplateSource {
+    fn inherit(self, base: &TemplateSource) -> TemplateSource {
+        TemplateSource {
+            name: self.name,
+            inherits: base.inherits.clone(),
+            entity_type: self.entity_type.or_else(|| base.entity_type.clone()),
+            levels: self.levels.or_else(|| base.levels.clone()),
+            frequency: self.frequency.or(base.frequency),
+            ramp: self.ramp.or(base.ramp),
+            glyph: self.glyph.or(base.glyph),
+            color: self.color.or(base.color),
+            background: self.background.or(base.background),
+            render_order: self.render_order.or(base.render_order),
+            fov_radius: self.fov_radius.or(base.fov_radius),
+            perception: self.perception.or(base.perception),
+            asleep: self.asleep.or(base.asleep),
+            description: self.description.or_else(|| base.description.clone()),
+            effects: self.effects.or_else(|| base.effects.clone()),
+            hp: self.hp.or(base.hp),
+            base_damage: self.base_damage.or(base.base_damage),
+            damage_type: self.damage_type.or(base.damage_type),
+            attack: self.attack.or(base.attack),
+            defence: self.defence.or(base.defence),
+            resistances: self.resistances.or_else(|| base.resistances.clone()),
+            faction: self.faction.or_else(|| base.faction.clone()),
+            durability: self.durability.or(base.durability),
+            behaviours: self.behaviours.or_else(|| base.behaviours.clone()),
+            abilities: self.abilities.or_else(|| base.abilities.clone()),
+            phases: self.phases.or_else(|| base.phases.clone()),
+            provides: None,
+            ai: None,
+        }
+    }
+
+    fn upgrade_legacy(mut self, errors: &mut Vec<TemplateErrorKind>) -> TemplateSource {
+        if let Some(provides) = self.provides.take() {
+            let mut effects = self.effects.take().unwrap_or_default();
+            provides
+                .iter()
+                .for_each(|(name, n)| match Effect::from_legacy(name, *n) {
+                    Some(effect) => effects.push(effect),
+                    None => errors.push(TemplateErrorKind::UnknownEffect(name.clone())),
+                });
+            self.effects = Some(effects);
+        }
+        if let Some(ai) = self.ai.take() {
+            let mut behaviours = self.behaviours.take().unwrap_or_default();
+            ai.iter()
+                .for_each(|name| match Behaviour::from_legacy(name) {
+                    Some(behaviour) => behaviours.push(behaviour),
+                    None => errors.push(TemplateErrorKind::UnknownAi(name.clone())),
+                });
+            self.behaviours = Some(behaviours);
+        }
+        self
+    }
+
+    fn into_template(self) -> Result<Template, TemplateErrorKind> {
+        Ok(Template {
+            entity_type: self
+                .entity_type
+                .ok_or(TemplateErrorKind::MissingField("entity_type"))?,
+            levels: self
+                .levels
+                .ok_or(TemplateErrorKind::MissingField("levels"))?,
+            frequency: self
+                .frequency
+                .ok_or(TemplateErrorKind::MissingField("frequency"))?,
+            ramp: self.ramp,
+            name: self.name,
+            glyph: self.glyph.ok_or(TemplateErrorKind::MissingField("glyph"))?,
+            color: self.color,
+            background: self.background,
+            render_order: self.render_order,
+            fov_radius: self.fov_radius,
+            perception: self.perception,
+            asleep: self.asleep,
+            description: self.description,
+            effects: self.effects.unwrap_or_default(),
+            hp: self.hp,
+            base_damage: self.base_damage,
+            damage_type: self.damage_type,
+            attack: self.attack,
+            defence: self.defence,
+            resistances: self.resistances,
+            faction: self.faction,
+            durability: self.durability,
+            behaviours: self.behaviours.unwrap_or_default(),
+            abilities: self.abilities,
+            phases: self.phases,
+            affixed: false,
+        })
+    }
+}
+
+impl TemplatesSource {
+    pub fn resolve(self, source: &str) -> (Templates, Vec<TemplateError>) {
+        let mut errors = Vec::new();
+        let mut lookup: HashMap<&str, &TemplateSource> = HashMap::new();
+        self.entities
+            .iter()
+            .chain(self.bases.iter().flatten())
+            .for_each(|t| {
+                lookup.insert(t.name.as_str(), t);
+            });
+
+        let mut seen = HashMap::<&str, usize>::new();
+        let mut entities = Vec::new();
+        self.entities.iter().for_each(|template| {
+            let occurrence = *seen
+                .entry(template.name.as_str())
+                .and_modify(|n| *n += 1)
+                .or_insert(0);
+            let mut kinds = Vec::new();
+            let resolved = resolve_chain(template, &lookup, &mut kinds)
+                .and_then(|t| t.into_template().map_err(|kind| kinds.push(kind)).ok());
+            kinds.into_iter().for_each(|kind| {
+                errors.push(TemplateError {
+                    template: Some(template.name.clone()),
+                    position: name_position(source, &template.name, occurrence),
+                    kind,
+                })
+            });
+            if let Some(resolved) = resolved {
+                entities.push(resolved);
+            }
+        });
+
+        let templates = Templates {
+            entities,
+            groups: self.groups,
+            affixes: self.affixes.unwrap_or_default(),
+            budgets: self.budgets.unwrap_or_default(),
+            guarantees: self.guarantees.unwrap_or_default(),
+            factions: self.factions.unwrap_or_default(),
+            difficulty: Default::default(),
+        };
+        (templates, errors)
+    }
+}
+
+fn resolve_chain(
+    template: &TemplateSource,
+    lookup: &HashMap<&str, &TemplateSource>,
+    errors: &mut Vec<TemplateErrorKind>,
+) -> Option<TemplateSource> {
+    let mut chain = vec![template.name.clone()];
+    let mut merged = template.clone().upgrade_legacy(errors);
+    while let Some(base_name) = merged.inherits.clone() {
+        if chain.contains(&base_name) {
+            chain.push(base_name);
+            errors.push(TemplateErrorKind::InheritanceCycle(chain.join(" -> ")));
+            return None;
+        }
+        let base = match lookup.get(base_name.as_str()) {
+            Some(base) => (*base).clone().upgrade_legacy(&mut Vec::new()),
+            None => {
+                errors.push(TemplateErrorKind::UnknownBase(base_name));
+                return None;
+            }
+        };
+        chain.push(base_name);
+        merged = merged.inherit(&base);
+    }
+    Some(merged)
+}
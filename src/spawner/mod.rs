@@ -1,9 +1,19 @@
 use crate::prelude::*;
+use std::collections::BTreeMap;
 
+mod inheritance;
+mod report;
+mod spawn_table;
 mod template;
-use template::Templates;
+mod validation;
+pub use template::{Templates, DEFAULT_FACTION, PLAYER_FACTION};
 
-pub fn spawn_player(ecs: &mut World, pos: Point) {
+const TORCH_COLOR: (u8, u8, u8) = (255, 180, 80);
+const TORCH_SPACING: i32 = 12;
+const PLAYER_ATTACK: i32 = 2;
+const PLAYER_DEFENCE: i32 = 1;
+
+pub fn spawn_player(ecs: &mut World, pos: Point, difficulty: &Difficulty) {
     ecs.push((
         Player {
             map_level: 0,
@@ -13,35 +23,174 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
         Render {
             color: ColorPair::new(WHITE, BLACK),
             glyph: to_cp437('@'),
+            order: 2,
         },
         Health {
-            current: 10,
-            max: 10,
+            current: difficulty.player_hp,
+            max: difficulty.player_hp,
         },
         FieldOfView::new(8),
-        Damage(1),
+        Damage(Dice::fixed(difficulty.player_damage)),
+        Faction(PLAYER_FACTION.to_string()),
+        CombatStats {
+            attack: PLAYER_ATTACK,
+            defence: PLAYER_DEFENCE,
+        },
     ));
 }
 
 pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
-    ecs.push((
+    ecs.push(amulet_of_yala(pos));
+}
+
+pub fn drop_amulet_of_yala(commands: &mut CommandBuffer, pos: Point) {
+    commands.push(amulet_of_yala(pos));
+}
+
+fn amulet_of_yala(pos: Point) -> (Item, AmuletOfYala, Point, Render, Name) {
+    (
         Item,
         AmuletOfYala,
         pos,
         Render {
-            color: ColorPair::new(WHITE, BLACK),
+            color: ColorPair::new(GOLD, BLACK),
             glyph: to_cp437('|'),
+            order: 0,
         },
         Name("Amulet of Yala".to_string()),
-    ));
+    )
 }
 
 pub fn spawn_level(
     ecs: &mut World,
+    templates: &Templates,
     rng: &mut RandomNumberGenerator,
     level: usize,
-    spawn_points: &[Point],
+    map_builder: &MapBuilder,
 ) {
-    let template = Templates::load();
-    template.spawn_entities(ecs, rng, level, spawn_points);
+    templates.spawn_entities(ecs, rng, level, map_builder);
+    templates.spawn_treasure(
+        ecs,
+        rng,
+        level,
+        &map_builder.treasure_spawns,
+        &map_builder.theme,
+    );
+    spawn_torches(ecs, rng, map_builder);
+}
+
+fn spawn_torches(ecs: &mut World, rng: &mut RandomNumberGenerator, map_builder: &MapBuilder) {
+    let chance = map_builder.theme.torches.unwrap_or(0.0);
+    let map = &map_builder.map;
+    let mut areas: Vec<Vec<Point>> = map_builder
+        .rooms
+        .iter()
+        .map(|room| room.tiles.clone())
+        .collect();
+    if areas.is_empty() {
+        let mut sections: BTreeMap<(i32, i32), Vec<Point>> = BTreeMap::new();
+        (0..map.tiles.len())
+            .map(|idx| map.index_to_point2d(idx))
+            .filter(|pt| map.can_enter_tile(*pt))
+            .for_each(|pt| {
+                sections
+                    .entry((pt.y / TORCH_SPACING, pt.x / TORCH_SPACING))
+                    .or_default()
+                    .push(pt)
+            });
+        areas = sections.into_values().collect();
+    }
+
+    areas
+        .iter()
+        .filter_map(|tiles| {
+            if rng.range(0.0, 1.0) >= chance {
+                return None;
+            }
+            let walls: Vec<Point> = tiles
+                .iter()
+                .map(|pt| *pt + Point::new(0, -1))
+                .filter(|pt| {
+                    map.in_bounds(*pt) && map.tiles[map.point2d_to_index(*pt)] == TileType::Wall
+                })
+                .collect();
+            rng.random_slice_entry(&walls).copied()
+        })
+        .collect::<Vec<Point>>()
+        .into_iter()
+        .for_each(|pos| {
+            ecs.push((
+                pos,
+                Render {
+                    color: ColorPair::new(TORCH_COLOR, BLACK),
+                    glyph: to_cp437('/'),
+                    order: 0,
+                },
+                Name("Torch".to_string()),
+                LightSource {
+                    radius: 5,
+                    color: RGB::named(TORCH_COLOR),
+                },
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(area: Rect, kind: RoomKind) -> Room {
+        Room {
+            area,
+            tiles: area.point_set().into_iter().collect(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn lairs_get_monsters_and_treasure_rooms_get_items() {
+        let config = GameConfig::load().unwrap();
+        let templates = Templates::load(&config).unwrap();
+        let lair = Rect::with_size(2, 2, 6, 6);
+        let treasure = Rect::with_size(30, 2, 6, 6);
+        let mb = MapBuilder {
+            map: Map::new(SCREEN_WIDTH, SCREEN_HEIGHT),
+            rooms: vec![
+                room(lair, RoomKind::Lair),
+                room(treasure, RoomKind::Treasure),
+            ],
+            monster_spawns: vec![
+                Point::new(3, 3),
+                Point::new(5, 3),
+                Point::new(3, 5),
+                Point::new(5, 5),
+                Point::new(33, 5),
+            ],
+            treasure_spawns: vec![Point::new(32, 3), Point::new(34, 3)],
+            player_start: Point::new(60, 40),
+            amulet_start: Point::new(70, 40),
+            theme: Theme::default(),
+        };
+
+        let mut ecs = World::default();
+        let mut rng = RandomNumberGenerator::seeded(1);
+        spawn_level(&mut ecs, &templates, &mut rng, 0, &mb);
+
+        let in_room = |area: Rect, item: bool| {
+            <(&Point, Option<&Item>, Option<&Enemy>)>::query()
+                .iter(&ecs)
+                .filter(|(pos, _, _)| area.point_in_rect(**pos))
+                .filter(|(_, is_item, is_enemy)| {
+                    if item {
+                        is_item.is_some()
+                    } else {
+                        is_enemy.is_some()
+                    }
+                })
+                .count()
+        };
+        assert!(in_room(lair, false) >= 4);
+        assert_eq!(in_room(lair, true), 0);
+        assert!(in_room(treasure, true) >= 2);
+    }
 }
@@ -0,0 +1,236 @@
+use super::spawn_table::*;
+use super::template::*;
+use crate::prelude::*;
+use std::collections::HashSet;
+
+pub struct ReportRow {
+    pub depth: usize,
+    pub name: String,
+    pub kind: &'static str,
+    pub weight: f32,
+    pub pick_chance: f32,
+    pub expected: f32,
+    pub at_least_one: f32,
+}
+
+pub struct SpawnReport {
+    pub spawn_points: Vec<f32>,
+    pub rows: Vec<ReportRow>,
+    pub unreachable: Vec<String>,
+    pub empty_depths: Vec<usize>,
+}
+
+fn kind(choice: &SpawnChoice) -> &'static str {
+    match choice {
+        SpawnChoice::Single(template) => match template.entity_type {
+            EntityType::Item => "item",
+            EntityType::Enemy => "enemy",
+            EntityType::Boss => "boss",
+        },
+        SpawnChoice::Group(_) => "group",
+    }
+}
+
+impl Templates {
+    pub fn spawn_report(&self, config: &GameConfig, spawn_points: &[f32]) -> SpawnReport {
+        let mut rows = Vec::new();
+        let mut empty_depths = Vec::new();
+
+        spawn_points.iter().enumerate().for_each(|(depth, points)| {
+            let table = self.spawn_table(depth);
+            let mut depth_rows: Vec<ReportRow> = Vec::new();
+            let mut add = |choice: SpawnChoice, weight: f32, pick: f32, n: f32, chance: f32| {
+                let name = choice.name();
+                match depth_rows.iter_mut().find(|row| row.name == name) {
+                    Some(row) => {
+                        row.weight += weight;
+                        row.pick_chance += pick;
+                        row.expected += n;
+                        row.at_least_one = 1.0 - (1.0 - row.at_least_one) * (1.0 - chance);
+                    }
+                    None => depth_rows.push(ReportRow {
+                        depth,
+                        name: name.to_string(),
+                        kind: kind(&choice),
+                        weight,
+                        pick_chance: pick,
+                        expected: n,
+                        at_least_one: chance,
+                    }),
+                }
+            };
+
+            let (mut guaranteed_items, mut guaranteed_monsters) = (0.0, 0.0);
+            self.guarantees_at(depth).for_each(|guarantee| {
+                let choices: Vec<&Template> = guarantee
+                    .any_of
+                    .iter()
+                    .filter_map(|name| self.find(name))
+                    .collect();
+                choices.iter().for_each(|template| {
+                    let share = guarantee.count as f32 / choices.len() as f32;
+                    let chance =
+                        1.0 - (1.0 - 1.0 / choices.len() as f32).powi(guarantee.count as i32);
+                    add(SpawnChoice::Single(template), 0.0, 0.0, share, chance);
+                    match template.entity_type {
+                        EntityType::Item => guaranteed_items += share,
+                        _ => guaranteed_monsters += share,
+                    }
+                });
+            });
+
+            let remaining = f32::max(0.0, points - guaranteed_items - guaranteed_monsters);
+            let mut pick_from = |choices: &[(SpawnChoice, f32)], n: f32| {
+                let total = SpawnTable::total(choices);
+                choices.iter().for_each(|(choice, weight)| {
+                    let pick = weight / total;
+                    let chance = 1.0 - (1.0 - pick).powf(n);
+                    add(*choice, *weight, pick, n * pick, chance);
+                });
+            };
+            match self.budget(depth) {
+                Some((items, monsters)) => {
+                    let items = f32::min(f32::max(0.0, items as f32 - guaranteed_items), remaining);
+                    let monsters = f32::min(
+                        f32::max(0.0, monsters as f32 - guaranteed_monsters),
+                        remaining - items,
+                    );
+                    pick_from(&table.items, items);
+                    pick_from(&table.monsters, monsters);
+                }
+                None => {
+                    let combined: Vec<(SpawnChoice, f32)> = table
+                        .items
+                        .iter()
+                        .chain(table.monsters.iter())
+                        .copied()
+                        .collect();
+                    pick_from(&combined, remaining);
+                }
+            }
+
+            if !depth_rows
+                .iter()
+                .any(|row| row.kind != "item" && row.expected > 0.0)
+            {
+                empty_depths.push(depth);
+            }
+            rows.extend(depth_rows);
+        });
+
+        let unreachable = self.unreachable(config, &rows);
+        SpawnReport {
+            spawn_points: spawn_points.to_vec(),
+            rows,
+            unreachable,
+            empty_depths,
+        }
+    }
+
+    fn unreachable(&self, config: &GameConfig, rows: &[ReportRow]) -> Vec<String> {
+        let mut reachable: HashSet<String> = rows
+            .iter()
+            .filter(|row| row.expected > 0.0)
+            .map(|row| row.name.clone())
+            .collect();
+        if let Some(boss) = self.find_boss(config.amulet_level) {
+            reachable.insert(boss.name.clone());
+        }
+
+        loop {
+            let mut found = Vec::new();
+            self.groups
+                .iter()
+                .flatten()
+                .filter(|group| reachable.contains(&group.name))
+                .for_each(|group| {
+                    found.extend(group.leader.iter().chain(group.members.iter()).cloned())
+                });
+            self.entities
+                .iter()
+                .filter(|template| reachable.contains(&template.name))
+                .for_each(|template| {
+                    let phase_abilities = template
+                        .phases
+                        .iter()
+                        .flatten()
+                        .flat_map(|phase| phase.abilities.iter());
+                    template
+                        .abilities
+                        .iter()
+                        .flatten()
+                        .chain(phase_abilities)
+                        .for_each(|ability| found.extend(ability.summons().map(str::to_string)));
+                });
+
+            let before = reachable.len();
+            reachable.extend(found);
+            if reachable.len() == before {
+                break;
+            }
+        }
+
+        self.entities
+            .iter()
+            .map(|template| &template.name)
+            .chain(self.groups.iter().flatten().map(|group| &group.name))
+            .filter(|name| !reachable.contains(*name))
+            .cloned()
+            .collect()
+    }
+}
+
+impl SpawnReport {
+    pub fn print_table(&self) {
+        self.spawn_points
+            .iter()
+            .enumerate()
+            .for_each(|(depth, points)| {
+                println!("Depth {} (~{:.1} spawn points)", depth, points);
+                println!(
+                    "  {:<24} {:<6} {:>7} {:>7} {:>9} {:>7}",
+                    "template", "kind", "weight", "pick%", "expected", ">=1 %"
+                );
+                self.rows
+                    .iter()
+                    .filter(|row| row.depth == depth)
+                    .for_each(|row| {
+                        println!(
+                            "  {:<24} {:<6} {:>7.2} {:>7.1} {:>9.2} {:>7.1}",
+                            row.name,
+                            row.kind,
+                            row.weight,
+                            row.pick_chance * 100.0,
+                            row.expected,
+                            row.at_least_one * 100.0
+                        )
+                    });
+                if self.empty_depths.contains(&depth) {
+                    println!("  WARNING: no enemies spawn at this depth");
+                }
+                println!();
+            });
+
+        self.unreachable
+            .iter()
+            .for_each(|name| println!("WARNING: {} can never spawn", name));
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("depth,template,kind,weight,pick_chance,expected,at_least_one\n");
+        self.rows.iter().for_each(|row| {
+            csv.push_str(&format!(
+                "{},\"{}\",{},{},{},{},{}\n",
+                row.depth,
+                row.name,
+                row.kind,
+                row.weight,
+                row.pick_chance,
+                row.expected,
+                row.at_least_one
+            ));
+        });
+        csv
+    }
+}
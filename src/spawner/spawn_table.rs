@@ -0,0 +1,143 @@
+use super::template::*;
+use crate::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct SpawnBudget {
+    pub depth: usize,
+    pub monsters: usize,
+    pub items: usize,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Guarantee {
+    pub any_of: Vec<String>,
+    pub levels: HashSet<usize>,
+    pub count: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SpawnChoice<'a> {
+    Single(&'a Template),
+    Group(&'a GroupTemplate),
+}
+
+impl<'a> SpawnChoice<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            SpawnChoice::Single(template) => &template.name,
+            SpawnChoice::Group(group) => &group.name,
+        }
+    }
+}
+
+pub struct SpawnTable<'a> {
+    pub monsters: Vec<(SpawnChoice<'a>, f32)>,
+    pub items: Vec<(SpawnChoice<'a>, f32)>,
+}
+
+impl SpawnTable<'_> {
+    pub fn total(choices: &[(SpawnChoice, f32)]) -> f32 {
+        choices.iter().map(|(_, weight)| weight).sum()
+    }
+
+    pub fn restrict(&mut self, theme: &Theme) {
+        let restrict = |choices: &mut Vec<(SpawnChoice, f32)>, pool: &Option<Vec<String>>| {
+            if let Some(names) = pool {
+                let allowed: Vec<(SpawnChoice, f32)> = choices
+                    .iter()
+                    .filter(|(choice, _)| names.iter().any(|name| name == choice.name()))
+                    .copied()
+                    .collect();
+                if !allowed.is_empty() {
+                    *choices = allowed;
+                }
+            }
+        };
+        restrict(&mut self.monsters, &theme.monsters);
+        restrict(&mut self.items, &theme.items);
+    }
+}
+
+pub fn depth_weight(
+    levels: &HashSet<usize>,
+    frequency: i32,
+    ramp: Option<f32>,
+    level: usize,
+) -> f32 {
+    if !levels.contains(&level) {
+        return 0.0;
+    }
+    let shallowest = levels.iter().min().copied().unwrap_or(level);
+    f32::max(
+        0.0,
+        frequency as f32 + ramp.unwrap_or(0.0) * (level - shallowest) as f32,
+    )
+}
+
+impl Templates {
+    pub fn spawn_table(&self, level: usize) -> SpawnTable<'_> {
+        let mut table = SpawnTable {
+            monsters: Vec::new(),
+            items: Vec::new(),
+        };
+        self.entities
+            .iter()
+            .filter(|t| t.entity_type != EntityType::Boss)
+            .map(|t| (t, depth_weight(&t.levels, t.frequency, t.ramp, level)))
+            .filter(|(_, weight)| *weight > 0.0)
+            .for_each(|(t, weight)| match t.entity_type {
+                EntityType::Item => table.items.push((
+                    SpawnChoice::Single(t),
+                    weight * self.difficulty.item_frequency,
+                )),
+                _ => table.monsters.push((SpawnChoice::Single(t), weight)),
+            });
+        self.groups
+            .iter()
+            .flatten()
+            .map(|g| (g, depth_weight(&g.levels, g.frequency, g.ramp, level)))
+            .filter(|(_, weight)| *weight > 0.0)
+            .for_each(|(g, weight)| table.monsters.push((SpawnChoice::Group(g), weight)));
+        table
+    }
+
+    pub fn budget(&self, level: usize) -> Option<(usize, usize)> {
+        self.budgets
+            .iter()
+            .filter(|budget| budget.depth <= level)
+            .max_by_key(|budget| budget.depth)
+            .map(|budget| {
+                (
+                    (budget.items as f32 * self.difficulty.item_frequency).round() as usize,
+                    (budget.monsters as f32 * self.difficulty.monster_count).round() as usize,
+                )
+            })
+    }
+
+    pub fn guarantees_at(&self, level: usize) -> impl Iterator<Item = &Guarantee> {
+        self.guarantees
+            .iter()
+            .filter(move |guarantee| guarantee.levels.contains(&level))
+    }
+}
+
+pub fn pick_weighted<'a, T>(
+    choices: &'a [(T, f32)],
+    total_weight: f32,
+    rng: &mut RandomNumberGenerator,
+) -> Option<&'a T> {
+    if total_weight <= 0.0 {
+        return None;
+    }
+    let mut roll = rng.range(0.0, total_weight);
+    choices
+        .iter()
+        .find(|(_, weight)| {
+            roll -= weight;
+            roll < 0.0
+        })
+        .or_else(|| choices.last())
+        .map(|(choice, _)| choice)
+}
@@ -1,38 +1,338 @@
+use super::inheritance::TemplatesSource;
+use super::spawn_table::*;
+use super::validation::*;
 use crate::prelude::*;
-use ron::de::from_reader;
+use ron::de::from_str;
 use serde::Deserialize;
-use std::collections::HashSet;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::fs::read_to_string;
 
-#[derive(Clone, Deserialize, Debug)]
+pub const PLAYER_FACTION: &str = "Player";
+pub const DEFAULT_FACTION: &str = "Monsters";
+const DEFAULT_SUMMON_LIMIT: i32 = 3;
+const DEFAULT_WANDER_CHANCE: f32 = 1.0;
+const DEFAULT_GIVE_UP_AFTER: i32 = 0;
+const DEFAULT_TUNNEL_DETOUR: f32 = 2.0;
+
+#[derive(Clone, Debug)]
 pub struct Template {
     pub entity_type: EntityType,
     pub levels: HashSet<usize>,
     pub frequency: i32,
+    pub ramp: Option<f32>,
     pub name: String,
     pub glyph: char,
-    pub provides: Option<Vec<(String, i32)>>,
+    pub color: Option<(u8, u8, u8)>,
+    pub background: Option<(u8, u8, u8)>,
+    pub render_order: Option<i32>,
+    pub fov_radius: Option<i32>,
+    pub perception: Option<i32>,
+    pub asleep: Option<bool>,
+    pub description: Option<String>,
+    pub effects: Vec<Effect>,
     pub hp: Option<i32>,
-    pub base_damage: Option<i32>,
+    pub base_damage: Option<Dice>,
+    pub damage_type: Option<DamageType>,
+    pub attack: Option<i32>,
+    pub defence: Option<i32>,
+    pub resistances: Option<HashMap<DamageType, i32>>,
+    pub faction: Option<String>,
     pub durability: Option<i32>,
-    pub ai: Option<Vec<String>>,
+    pub behaviours: Vec<Behaviour>,
+    pub abilities: Option<Vec<AbilityTemplate>>,
+    pub phases: Option<Vec<PhaseTemplate>>,
+    pub affixed: bool,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum Effect {
+    Healing(i32),
+    MagicMap,
+    Digging,
+    Charm,
+    Vision(i32),
+    Light(i32),
+}
+
+impl Effect {
+    pub fn from_legacy(name: &str, amount: i32) -> Option<Self> {
+        match name {
+            "Healing" => Some(Effect::Healing(amount)),
+            "MagicMap" => Some(Effect::MagicMap),
+            "Digging" => Some(Effect::Digging),
+            "Charm" => Some(Effect::Charm),
+            "Vision" => Some(Effect::Vision(amount)),
+            "Light" => Some(Effect::Light(amount)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum Behaviour {
+    Random { wander_chance: f32 },
+    Chase { give_up_after: i32 },
+    Tunnel { detour: f32 },
+}
+
+impl Behaviour {
+    pub fn from_legacy(name: &str) -> Option<Self> {
+        match name {
+            "Random" => Some(Behaviour::Random {
+                wander_chance: DEFAULT_WANDER_CHANCE,
+            }),
+            "Chase" => Some(Behaviour::Chase {
+                give_up_after: DEFAULT_GIVE_UP_AFTER,
+            }),
+            "Tunnel" => Some(Behaviour::Tunnel {
+                detour: DEFAULT_TUNNEL_DETOUR,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct PhaseTemplate {
+    pub health_percent: i32,
+    pub abilities: Vec<AbilityTemplate>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub enum AbilityTemplate {
+    Firebolt {
+        cooldown: i32,
+        range: i32,
+        damage: i32,
+    },
+    Breath {
+        cooldown: i32,
+        range: i32,
+        damage: i32,
+    },
+    Heal {
+        cooldown: i32,
+        amount: i32,
+    },
+    Summon {
+        cooldown: i32,
+        template: String,
+        limit: Option<i32>,
+    },
+}
+
+impl AbilityTemplate {
+    pub fn to_ability(&self) -> Ability {
+        let (kind, range, cooldown) = match self {
+            AbilityTemplate::Firebolt {
+                cooldown,
+                range,
+                damage,
+            } => (AbilityKind::Firebolt { damage: *damage }, *range, *cooldown),
+            AbilityTemplate::Breath {
+                cooldown,
+                range,
+                damage,
+            } => (AbilityKind::Breath { damage: *damage }, *range, *cooldown),
+            AbilityTemplate::Heal { cooldown, amount } => {
+                (AbilityKind::Heal { amount: *amount }, 1, *cooldown)
+            }
+            AbilityTemplate::Summon {
+                cooldown,
+                template,
+                limit,
+            } => (
+                AbilityKind::Summon {
+                    template: template.clone(),
+                    limit: limit.unwrap_or(DEFAULT_SUMMON_LIMIT),
+                },
+                1,
+                *cooldown,
+            ),
+        };
+
+        Ability {
+            kind,
+            range,
+            cooldown,
+            ready_in: 0,
+        }
+    }
+
+    pub fn summons(&self) -> Option<&str> {
+        match self {
+            AbilityTemplate::Summon { template, .. } => Some(template),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
 pub enum EntityType {
     Enemy,
     Item,
+    Boss,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct FactionTemplate {
+    pub name: String,
+    pub hostile: Vec<String>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct GroupTemplate {
+    pub name: String,
+    pub levels: HashSet<usize>,
+    pub frequency: i32,
+    pub ramp: Option<f32>,
+    pub leader: Option<String>,
+    pub members: Vec<String>,
+    pub sizes: HashMap<usize, (i32, i32)>,
+}
+
+impl GroupTemplate {
+    fn size_range(&self, level: usize) -> (i32, i32) {
+        self.sizes
+            .iter()
+            .filter(|(depth, _)| **depth <= level)
+            .max_by_key(|(depth, _)| **depth)
+            .map(|(_, size)| *size)
+            .unwrap_or((1, 1))
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub enum AffixTarget {
+    Weapon,
+    Healing,
+    Enemy,
 }
 
 #[derive(Clone, Deserialize, Debug)]
+pub struct Affix {
+    pub name: String,
+    pub applies_to: AffixTarget,
+    pub chance: HashMap<usize, i32>,
+    pub damage: Option<i32>,
+    pub durability: Option<i32>,
+    pub healing: Option<i32>,
+    pub hp: Option<i32>,
+}
+
+impl Affix {
+    fn applies(&self, template: &Template) -> bool {
+        if template.affixed {
+            return false;
+        }
+        match self.applies_to {
+            AffixTarget::Weapon => {
+                template.entity_type == EntityType::Item && template.base_damage.is_some()
+            }
+            AffixTarget::Healing => template
+                .effects
+                .iter()
+                .any(|e| matches!(e, Effect::Healing(_))),
+            AffixTarget::Enemy => template.entity_type == EntityType::Enemy,
+        }
+    }
+
+    fn chance_at(&self, level: usize) -> i32 {
+        self.chance
+            .iter()
+            .filter(|(depth, _)| **depth <= level)
+            .max_by_key(|(depth, _)| **depth)
+            .map(|(_, chance)| *chance)
+            .unwrap_or(0)
+    }
+
+    fn apply(&self, template: &Template) -> Template {
+        let adjust = |value: Option<i32>, by: Option<i32>| match (value, by) {
+            (Some(value), Some(by)) => Some(i32::max(1, value + by)),
+            (value, _) => value,
+        };
+
+        let mut affixed = template.clone();
+        affixed.name = format!("{} {}", self.name, template.name);
+        affixed.affixed = true;
+        affixed.base_damage = template
+            .base_damage
+            .map(|dice| dice.plus(self.damage.unwrap_or(0)));
+        affixed.durability = adjust(template.durability, self.durability);
+        affixed.hp = adjust(template.hp, self.hp);
+        affixed.effects.iter_mut().for_each(|effect| {
+            if let Effect::Healing(n) = effect {
+                *n = adjust(Some(*n), self.healing).unwrap();
+            }
+        });
+        affixed
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Templates {
     pub entities: Vec<Template>,
+    pub groups: Option<Vec<GroupTemplate>>,
+    pub affixes: Vec<Affix>,
+    pub budgets: Vec<SpawnBudget>,
+    pub guarantees: Vec<Guarantee>,
+    pub factions: Vec<FactionTemplate>,
+    pub difficulty: Difficulty,
 }
 
 impl Templates {
-    pub fn load() -> Self {
-        let file = File::open("resources/template.ron").expect("Failed opening file");
-        from_reader(file).expect("Unable to load templates")
+    pub fn load(config: &GameConfig) -> Result<Self, Vec<TemplateError>> {
+        let source = read_to_string("resources/template.ron")
+            .map_err(|e| vec![TemplateError::new(TemplateErrorKind::Io(e.to_string()))])?;
+        Self::parse(&source, config)
+    }
+
+    pub fn parse(source: &str, config: &GameConfig) -> Result<Self, Vec<TemplateError>> {
+        let templates_source: TemplatesSource = from_str(source).map_err(|e| {
+            vec![TemplateError {
+                template: None,
+                position: Some(e.position).filter(|p| p.line > 0),
+                kind: TemplateErrorKind::Parse(e.code.to_string()),
+            }]
+        })?;
+        let (mut templates, mut errors) = templates_source.resolve(source);
+        templates.difficulty = config.difficulty();
+
+        errors.append(&mut validate(&templates, config, source));
+        if errors.is_empty() {
+            Ok(templates)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Template> {
+        self.entities.iter().find(|t| t.name == name)
+    }
+
+    pub fn hostile(&self, a: &str, b: &str) -> bool {
+        if a == b {
+            return false;
+        }
+        if a == PLAYER_FACTION || b == PLAYER_FACTION {
+            return true;
+        }
+        self.factions.iter().any(|faction| {
+            (faction.name == a && faction.hostile.iter().any(|h| h == b))
+                || (faction.name == b && faction.hostile.iter().any(|h| h == a))
+        })
+    }
+
+    pub fn find_boss(&self, level: usize) -> Option<&Template> {
+        self.entities
+            .iter()
+            .find(|t| t.entity_type == EntityType::Boss && t.levels.contains(&level))
+    }
+
+    pub fn spawn_boss(&self, ecs: &mut World, boss: &Template, pos: Point) {
+        let mut commands = CommandBuffer::new(ecs);
+        self.spawn_entity(&pos, boss, &mut commands);
+        commands.flush(ecs);
     }
 
     pub fn spawn_entities(
@@ -40,83 +340,398 @@ impl Templates {
         ecs: &mut World,
         rng: &mut RandomNumberGenerator,
         level: usize,
-        spawn_points: &[Point],
+        map_builder: &MapBuilder,
     ) {
-        let mut available_entities = Vec::new();
-        self.entities
+        let map = &map_builder.map;
+        let mut table = self.spawn_table(level);
+        table.restrict(&map_builder.theme);
+        let lair_tiles: HashSet<Point> = map_builder
+            .rooms
+            .iter()
+            .filter(|room| room.kind == RoomKind::Lair)
+            .flat_map(|room| room.tiles.iter().copied())
+            .collect();
+        let (lair_points, room_points): (Vec<Point>, Vec<Point>) = map_builder
+            .monster_spawns
             .iter()
-            .filter(|e| e.levels.contains(&level))
-            .for_each(|t| {
-                for _ in 0..t.frequency {
-                    available_entities.push(t);
+            .partition(|pt| lair_tiles.contains(pt));
+        let mut points = room_points.iter();
+        let mut lair = lair_points.iter();
+        let mut occupied: HashSet<Point> = map_builder.monster_spawns.iter().copied().collect();
+        let mut commands = CommandBuffer::new(ecs);
+        let mut spawn =
+            |choice: &SpawnChoice, pt: &Point, rng: &mut RandomNumberGenerator| match choice {
+                SpawnChoice::Single(template) => {
+                    match self.roll_affix(template, level, rng) {
+                        Some(affixed) => self.spawn_entity(pt, &affixed, &mut commands),
+                        None => self.spawn_entity(pt, template, &mut commands),
+                    };
+                }
+                SpawnChoice::Group(group) => {
+                    self.spawn_group(pt, group, level, rng, map, &mut occupied, &mut commands);
+                }
+            };
+
+        let (mut items, mut monsters) = self.budget(level).unwrap_or((0, 0));
+        self.guarantees_at(level).for_each(|guarantee| {
+            let choices: Vec<&Template> = guarantee
+                .any_of
+                .iter()
+                .filter_map(|name| self.find(name))
+                .collect();
+            for _ in 0..guarantee.count {
+                let template = match rng.random_slice_entry(&choices) {
+                    Some(template) => *template,
+                    None => return,
+                };
+                let pt = match template.entity_type {
+                    EntityType::Item => points.next(),
+                    _ => lair.next().or_else(|| points.next()),
+                };
+                if let Some(pt) = pt {
+                    spawn(&SpawnChoice::Single(template), pt, rng);
+                    match template.entity_type {
+                        EntityType::Item => items = items.saturating_sub(1),
+                        _ => monsters = monsters.saturating_sub(1),
+                    }
+                }
+            }
+        });
+
+        if self.budget(level).is_some() {
+            let item_weight = SpawnTable::total(&table.items);
+            let monster_weight = SpawnTable::total(&table.monsters);
+            points.by_ref().take(items).for_each(|pt| {
+                if let Some(choice) = pick_weighted(&table.items, item_weight, rng) {
+                    spawn(choice, pt, rng);
+                }
+            });
+            lair.chain(points).take(monsters).for_each(|pt| {
+                if let Some(choice) = pick_weighted(&table.monsters, monster_weight, rng) {
+                    spawn(choice, pt, rng);
+                }
+            });
+        } else {
+            let monster_weight = SpawnTable::total(&table.monsters);
+            lair.for_each(|pt| {
+                if let Some(choice) = pick_weighted(&table.monsters, monster_weight, rng) {
+                    spawn(choice, pt, rng);
                 }
             });
+            let combined: Vec<(SpawnChoice, f32)> = table
+                .items
+                .iter()
+                .chain(table.monsters.iter())
+                .copied()
+                .collect();
+            let total_weight = SpawnTable::total(&combined);
+            points.for_each(|pt| {
+                if let Some(choice) = pick_weighted(&combined, total_weight, rng) {
+                    spawn(choice, pt, rng);
+                }
+            });
+        }
+        commands.flush(ecs);
+    }
 
+    pub fn spawn_treasure(
+        &self,
+        ecs: &mut World,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        treasure_points: &[Point],
+        theme: &Theme,
+    ) {
+        let mut table = self.spawn_table(level);
+        table.restrict(theme);
+        let total_weight = SpawnTable::total(&table.items);
         let mut commands = CommandBuffer::new(ecs);
-        spawn_points.iter().for_each(|pt| {
-            if let Some(entity) = rng.random_slice_entry(&available_entities) {
-                self.spawn_entity(pt, entity, &mut commands);
+        treasure_points.iter().for_each(|pt| {
+            if let Some(SpawnChoice::Single(template)) =
+                pick_weighted(&table.items, total_weight, rng)
+            {
+                match self.roll_affix(template, level, rng) {
+                    Some(affixed) => self.spawn_entity(pt, &affixed, &mut commands),
+                    None => self.spawn_entity(pt, template, &mut commands),
+                };
             }
         });
         commands.flush(ecs);
     }
 
-    fn spawn_entity(&self, pt: &Point, template: &Template, commands: &mut CommandBuffer) {
+    fn roll_affix(
+        &self,
+        template: &Template,
+        level: usize,
+        rng: &mut RandomNumberGenerator,
+    ) -> Option<Template> {
+        self.affixes
+            .iter()
+            .filter(|affix| affix.applies(template))
+            .find(|affix| rng.range(0, 100) < affix.chance_at(level))
+            .map(|affix| affix.apply(template))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_group(
+        &self,
+        pt: &Point,
+        group: &GroupTemplate,
+        level: usize,
+        rng: &mut RandomNumberGenerator,
+        map: &Map,
+        occupied: &mut HashSet<Point>,
+        commands: &mut CommandBuffer,
+    ) {
+        let at_depth = |name: &&String| self.find(name).is_some_and(|t| t.levels.contains(&level));
+        let (min_size, max_size) = group.size_range(level);
+        let members: Vec<&String> = group.members.iter().filter(at_depth).collect();
+        let mut names: Vec<&String> = group.leader.iter().filter(at_depth).collect();
+        for _ in 0..rng.range(min_size, max_size + 1) {
+            if let Some(name) = rng.random_slice_entry(&members) {
+                names.push(name);
+            }
+        }
+
+        let mut pack = None;
+        let mut positions = vec![*pt];
+        positions.append(&mut cluster_points(
+            pt,
+            names.len().saturating_sub(1),
+            map,
+            occupied,
+        ));
+        names
+            .iter()
+            .zip(positions.iter())
+            .filter_map(|(name, pos)| self.find(name).map(|t| (t, pos)))
+            .for_each(|(template, pos)| {
+                let entity = match self.roll_affix(template, level, rng) {
+                    Some(affixed) => self.spawn_entity(pos, &affixed, commands),
+                    None => self.spawn_entity(pos, template, commands),
+                };
+                let id = *pack.get_or_insert(entity);
+                commands.add_component(
+                    entity,
+                    Pack {
+                        id,
+                        alerted: false,
+                        last_seen: None,
+                        memory: 0,
+                    },
+                );
+            });
+    }
+
+    pub fn spawn_entity(
+        &self,
+        pt: &Point,
+        template: &Template,
+        commands: &mut CommandBuffer,
+    ) -> Entity {
         let entity = commands.push((
             *pt,
             Render {
-                color: ColorPair::new(WHITE, BLACK),
+                color: ColorPair::new(
+                    template
+                        .color
+                        .map(RGB::named)
+                        .unwrap_or_else(|| RGB::named(WHITE)),
+                    template
+                        .background
+                        .map(RGB::named)
+                        .unwrap_or_else(|| RGB::named(BLACK)),
+                ),
                 glyph: to_cp437(template.glyph),
+                order: template.render_order.unwrap_or(match template.entity_type {
+                    EntityType::Item => 0,
+                    EntityType::Enemy | EntityType::Boss => 1,
+                }),
             },
             Name(template.name.clone()),
         ));
 
+        if let Some(description) = &template.description {
+            commands.add_component(entity, Description(description.clone()));
+        }
+
         match template.entity_type {
             EntityType::Item => commands.add_component(entity, Item {}),
-            EntityType::Enemy => {
+            EntityType::Enemy | EntityType::Boss => {
                 commands.add_component(entity, Enemy {});
-                commands.add_component(entity, FieldOfView::new(6));
+                commands.add_component(entity, FieldOfView::new(template.fov_radius.unwrap_or(6)));
+                if template.entity_type == EntityType::Enemy {
+                    commands.add_component(
+                        entity,
+                        Awareness {
+                            alertness: if template.asleep.unwrap_or(false) {
+                                Alertness::Asleep
+                            } else {
+                                Alertness::Unaware
+                            },
+                            perception: template.perception.unwrap_or(0),
+                        },
+                    );
+                }
+                let hp = Difficulty::scale(template.hp.unwrap(), self.difficulty.monster_hp);
                 commands.add_component(
                     entity,
                     Health {
-                        current: template.hp.unwrap(),
-                        max: template.hp.unwrap(),
+                        current: hp,
+                        max: hp,
                     },
                 );
-                if let Some(ai) = &template.ai {
-                    ai.iter().for_each(|ai_type| match ai_type.as_str() {
-                        "Random" => commands.add_component(entity, MovingRandomly {}),
-                        _ => commands.add_component(entity, ChasingPlayer {}),
-                    });
-                } else {
-                    commands.add_component(entity, ChasingPlayer {});
+                if !template
+                    .behaviours
+                    .iter()
+                    .any(|b| matches!(b, Behaviour::Random { .. } | Behaviour::Chase { .. }))
+                {
+                    commands.add_component(entity, ChasingPlayer::new(DEFAULT_GIVE_UP_AFTER));
                 }
+                template
+                    .behaviours
+                    .iter()
+                    .for_each(|behaviour| match *behaviour {
+                        Behaviour::Random { wander_chance } => {
+                            commands.add_component(entity, MovingRandomly { wander_chance })
+                        }
+                        Behaviour::Chase { give_up_after } => {
+                            commands.add_component(entity, ChasingPlayer::new(give_up_after))
+                        }
+                        Behaviour::Tunnel { detour } => {
+                            commands.add_component(entity, Tunnelling { detour })
+                        }
+                    });
             }
         }
 
-        if let Some(effects) = &template.provides {
-            effects
-                .iter()
-                .for_each(|(provides, n)| match provides.as_str() {
-                    "Healing" => commands.add_component(entity, ProvidesHealing { amount: *n }),
-                    "MagicMap" => commands.add_component(entity, ProvidesDungeonMap {}),
-                    "Digging" => commands.add_component(entity, ProvidesDigging {}),
-                    "Vision" => commands.add_component(entity, ProvidesVision { amount: *n }),
-                    _ => {
-                        println!("Warning: we don't know how to provide {}", provides);
-                    }
-                });
-        }
+        template.effects.iter().for_each(|effect| match effect {
+            Effect::Healing(n) => commands.add_component(
+                entity,
+                ProvidesHealing {
+                    amount: Difficulty::scale(*n, self.difficulty.healing),
+                },
+            ),
+            Effect::MagicMap => commands.add_component(entity, ProvidesDungeonMap {}),
+            Effect::Digging => commands.add_component(entity, ProvidesDigging {}),
+            Effect::Charm => commands.add_component(entity, ProvidesCharm {}),
+            Effect::Vision(n) => commands.add_component(entity, ProvidesVision { amount: *n }),
+            Effect::Light(n) => commands.add_component(
+                entity,
+                LightSource {
+                    radius: *n,
+                    color: template
+                        .color
+                        .map(RGB::named)
+                        .unwrap_or_else(|| RGB::named(WHITE)),
+                },
+            ),
+        });
 
         if let Some(damage) = &template.base_damage {
-            commands.add_component(entity, Damage(*damage));
             if template.entity_type == EntityType::Item {
+                commands.add_component(entity, Damage(*damage));
                 commands.add_component(entity, Weapon {});
+            } else {
+                let damage = damage.scaled(self.difficulty.monster_damage);
+                commands.add_component(entity, Damage(damage));
             }
         }
 
+        if let Some(kind) = template.damage_type {
+            commands.add_component(entity, DamageKind(kind));
+        }
+
+        if template.entity_type != EntityType::Item
+            || template.attack.is_some()
+            || template.defence.is_some()
+        {
+            commands.add_component(
+                entity,
+                CombatStats {
+                    attack: template.attack.unwrap_or(0),
+                    defence: template.defence.unwrap_or(0),
+                },
+            );
+        }
+
+        if template.entity_type != EntityType::Item {
+            commands.add_component(
+                entity,
+                Faction(
+                    template
+                        .faction
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_FACTION.to_string()),
+                ),
+            );
+        }
+
+        if let Some(resistances) = &template.resistances {
+            commands.add_component(entity, Resistances(resistances.clone()));
+        }
+
         if let Some(durability) = &template.durability {
             commands.add_component(entity, Durability(*durability));
         }
+
+        if template.entity_type == EntityType::Boss {
+            commands.add_component(entity, Boss);
+        }
+
+        if let Some(phases) = &template.phases {
+            commands.add_component(
+                entity,
+                BossPhases(
+                    phases
+                        .iter()
+                        .map(|phase| BossPhase {
+                            health_percent: phase.health_percent,
+                            abilities: phase.abilities.iter().map(|a| a.to_ability()).collect(),
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        if let Some(abilities) = &template.abilities {
+            commands.add_component(
+                entity,
+                Abilities(abilities.iter().map(|a| a.to_ability()).collect()),
+            );
+        }
+
+        entity
+    }
+}
+
+fn cluster_points(
+    center: &Point,
+    count: usize,
+    map: &Map,
+    occupied: &mut HashSet<Point>,
+) -> Vec<Point> {
+    const CLUSTER_RADIUS: i32 = 3;
+    let mut candidates = Vec::new();
+    for y in center.y - CLUSTER_RADIUS..=center.y + CLUSTER_RADIUS {
+        for x in center.x - CLUSTER_RADIUS..=center.x + CLUSTER_RADIUS {
+            let pt = Point::new(x, y);
+            if map.in_bounds(pt)
+                && map.tiles[map.point2d_to_index(pt)] == TileType::Floor
+                && !occupied.contains(&pt)
+            {
+                candidates.push(pt);
+            }
+        }
     }
+    candidates.sort_by(|a, b| {
+        let da = DistanceAlg::Pythagoras.distance2d(*center, *a);
+        let db = DistanceAlg::Pythagoras.distance2d(*center, *b);
+        da.partial_cmp(&db).unwrap()
+    });
+    candidates.truncate(count);
+    candidates.iter().for_each(|pt| {
+        occupied.insert(*pt);
+    });
+    candidates
 }
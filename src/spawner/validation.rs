@@ -0,0 +1,418 @@
+use super::template::{EntityType, Templates};
+use crate::prelude::*;
+use ron::error::Position;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateErrorKind {
+    Io(String),
+    Parse(String),
+    MissingHp,
+    MissingField(&'static str),
+    UnknownBase(String),
+    InheritanceCycle(String),
+    UnknownEffect(String),
+    UnknownAi(String),
+    UnknownSummon(String),
+    UnknownGroupMember(String),
+    UnknownGuarantee(String),
+    UnknownFaction(String),
+    EmptyGroup,
+    EmptyLevels,
+    ZeroFrequency,
+    DuplicateName,
+    GlyphOutsideCp437(char),
+    LevelTooDeep(usize),
+    BossOffAmuletLevel(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateError {
+    pub template: Option<String>,
+    pub position: Option<Position>,
+    pub kind: TemplateErrorKind,
+}
+
+impl TemplateError {
+    pub fn new(kind: TemplateErrorKind) -> Self {
+        Self {
+            template: None,
+            position: None,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "resources/template.ron")?;
+        if let Some(position) = &self.position {
+            write!(f, ":{}", position)?;
+        }
+        if let Some(template) = &self.template {
+            write!(f, ": {}", template)?;
+        }
+        match &self.kind {
+            TemplateErrorKind::Io(e) => write!(f, ": unable to read file ({})", e),
+            TemplateErrorKind::Parse(e) => write!(f, ": {}", e),
+            TemplateErrorKind::MissingHp => write!(f, ": enemies need an hp value"),
+            TemplateErrorKind::MissingField(field) => {
+                write!(f, ": {} is not set and not inherited", field)
+            }
+            TemplateErrorKind::UnknownBase(base) => {
+                write!(f, ": cannot inherit from unknown template \"{}\"", base)
+            }
+            TemplateErrorKind::InheritanceCycle(chain) => {
+                write!(f, ": inheritance cycle {}", chain)
+            }
+            TemplateErrorKind::UnknownEffect(e) => write!(f, ": unknown effect \"{}\"", e),
+            TemplateErrorKind::UnknownAi(a) => write!(f, ": unknown ai \"{}\"", a),
+            TemplateErrorKind::UnknownSummon(s) => {
+                write!(f, ": cannot summon unknown template \"{}\"", s)
+            }
+            TemplateErrorKind::UnknownGroupMember(m) => {
+                write!(f, ": unknown group member \"{}\"", m)
+            }
+            TemplateErrorKind::UnknownGuarantee(g) => {
+                write!(f, ": cannot guarantee unknown template \"{}\"", g)
+            }
+            TemplateErrorKind::UnknownFaction(faction) => {
+                write!(f, ": unknown faction \"{}\"", faction)
+            }
+            TemplateErrorKind::EmptyGroup => write!(f, ": group has no members"),
+            TemplateErrorKind::EmptyLevels => write!(f, ": levels must not be empty"),
+            TemplateErrorKind::ZeroFrequency => write!(f, ": frequency must be above zero"),
+            TemplateErrorKind::DuplicateName => write!(f, ": name is used more than once"),
+            TemplateErrorKind::GlyphOutsideCp437(c) => {
+                write!(f, ": glyph '{}' is not in codepage 437", c)
+            }
+            TemplateErrorKind::LevelTooDeep(level) => {
+                write!(f, ": level {} is deeper than the configured levels", level)
+            }
+            TemplateErrorKind::BossOffAmuletLevel(level) => {
+                write!(f, ": bosses can only guard the amulet on level {}", level)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+pub fn validate(templates: &Templates, config: &GameConfig, source: &str) -> Vec<TemplateError> {
+    let mut errors = Vec::new();
+    let known_faction = |faction: &str| {
+        faction == PLAYER_FACTION
+            || faction == DEFAULT_FACTION
+            || templates.factions.iter().any(|f| f.name == faction)
+    };
+    let mut seen = HashMap::<&str, usize>::new();
+    let mut report = |name: &str, occurrence: usize, kind: TemplateErrorKind| {
+        errors.push(TemplateError {
+            template: Some(name.to_string()),
+            position: name_position(source, name, occurrence),
+            kind,
+        });
+    };
+
+    templates.entities.iter().for_each(|template| {
+        let name = template.name.as_str();
+        let occurrence = *seen.entry(name).and_modify(|n| *n += 1).or_insert(0);
+        if occurrence > 0 {
+            report(name, occurrence, TemplateErrorKind::DuplicateName);
+        }
+
+        if template.entity_type != EntityType::Item && template.hp.is_none() {
+            report(name, occurrence, TemplateErrorKind::MissingHp);
+        }
+        if template.levels.is_empty() {
+            report(name, occurrence, TemplateErrorKind::EmptyLevels);
+        }
+        if let Some(faction) = template.faction.as_deref().filter(|f| !known_faction(f)) {
+            report(
+                name,
+                occurrence,
+                TemplateErrorKind::UnknownFaction(faction.to_string()),
+            );
+        }
+        if template.frequency < 1 && template.entity_type != EntityType::Boss {
+            report(name, occurrence, TemplateErrorKind::ZeroFrequency);
+        }
+        if to_cp437(template.glyph) == 0 && template.glyph != '\0' {
+            report(
+                name,
+                occurrence,
+                TemplateErrorKind::GlyphOutsideCp437(template.glyph),
+            );
+        }
+
+        let phase_abilities = template
+            .phases
+            .iter()
+            .flatten()
+            .flat_map(|phase| phase.abilities.iter());
+        template
+            .abilities
+            .iter()
+            .flatten()
+            .chain(phase_abilities)
+            .for_each(|ability| {
+                if let Some(summons) = ability.summons() {
+                    if templates.find(summons).is_none() {
+                        report(
+                            name,
+                            occurrence,
+                            TemplateErrorKind::UnknownSummon(summons.to_string()),
+                        );
+                    }
+                }
+            });
+
+        template
+            .levels
+            .iter()
+            .filter(|level| **level >= config.levels)
+            .for_each(|level| report(name, occurrence, TemplateErrorKind::LevelTooDeep(*level)));
+
+        if template.entity_type == EntityType::Boss
+            && template.levels.iter().any(|l| *l != config.amulet_level)
+        {
+            report(
+                name,
+                occurrence,
+                TemplateErrorKind::BossOffAmuletLevel(config.amulet_level),
+            );
+        }
+    });
+
+    templates.groups.iter().flatten().for_each(|group| {
+        let name = group.name.as_str();
+        let occurrence = *seen.entry(name).and_modify(|n| *n += 1).or_insert(0);
+        if occurrence > 0 {
+            report(name, occurrence, TemplateErrorKind::DuplicateName);
+        }
+
+        if group.members.is_empty() {
+            report(name, occurrence, TemplateErrorKind::EmptyGroup);
+        }
+        if group.levels.is_empty() {
+            report(name, occurrence, TemplateErrorKind::EmptyLevels);
+        }
+        if group.frequency < 1 {
+            report(name, occurrence, TemplateErrorKind::ZeroFrequency);
+        }
+        group
+            .leader
+            .iter()
+            .chain(group.members.iter())
+            .filter(|member| templates.find(member).is_none())
+            .for_each(|member| {
+                report(
+                    name,
+                    occurrence,
+                    TemplateErrorKind::UnknownGroupMember(member.clone()),
+                )
+            });
+        group
+            .levels
+            .iter()
+            .filter(|level| **level >= config.levels)
+            .for_each(|level| report(name, occurrence, TemplateErrorKind::LevelTooDeep(*level)));
+    });
+
+    templates.factions.iter().for_each(|faction| {
+        faction
+            .hostile
+            .iter()
+            .filter(|hostile| !known_faction(hostile))
+            .for_each(|hostile| {
+                errors.push(TemplateError {
+                    template: Some(faction.name.clone()),
+                    position: None,
+                    kind: TemplateErrorKind::UnknownFaction(hostile.clone()),
+                })
+            });
+    });
+
+    templates
+        .guarantees
+        .iter()
+        .flat_map(|guarantee| guarantee.any_of.iter())
+        .filter(|name| templates.find(name).is_none())
+        .for_each(|name| {
+            errors.push(TemplateError {
+                template: None,
+                position: None,
+                kind: TemplateErrorKind::UnknownGuarantee(name.clone()),
+            })
+        });
+
+    errors
+}
+
+pub fn name_position(source: &str, name: &str, occurrence: usize) -> Option<Position> {
+    let quoted = format!("\"{}\"", name);
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let col = text.find(&quoted)?;
+            let before = text[..col].trim_end().strip_suffix(':')?;
+            if before.trim_end().ends_with("name") {
+                Some(Position {
+                    line: line + 1,
+                    col: col + 1,
+                })
+            } else {
+                None
+            }
+        })
+        .nth(occurrence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(entities: &str) -> Vec<TemplateError> {
+        let source = format!("Templates(\n    entities: [\n{}    ],\n)\n", entities);
+        match Templates::parse(&source, &GameConfig::load().unwrap()) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors,
+        }
+    }
+
+    fn error(name: &str, line: usize, kind: TemplateErrorKind) -> TemplateError {
+        TemplateError {
+            template: Some(name.to_string()),
+            position: Some(Position { line, col: 19 }),
+            kind,
+        }
+    }
+
+    const GOBLIN: &str = r#"        Template(
+            name: "Goblin",
+            entity_type: Enemy, glyph: 'g', levels: [ 0 ], frequency: 1, hp: Some(1),
+        ),
+"#;
+
+    #[test]
+    fn valid_template_has_no_errors() {
+        assert_eq!(errors(GOBLIN), vec![]);
+    }
+
+    #[test]
+    fn missing_hp() {
+        let source = r#"        Template(
+            name: "Goblin",
+            entity_type: Enemy, glyph: 'g', levels: [ 0 ], frequency: 1,
+        ),
+"#;
+        assert_eq!(
+            errors(source),
+            vec![error("Goblin", 4, TemplateErrorKind::MissingHp)]
+        );
+    }
+
+    #[test]
+    fn unknown_effect() {
+        let source = r#"        Template(
+            name: "Potion",
+            entity_type: Item, glyph: '!', levels: [ 0 ], frequency: 1,
+            provides: Some([ ("Flying", 1) ]),
+        ),
+"#;
+        assert_eq!(
+            errors(source),
+            vec![error(
+                "Potion",
+                4,
+                TemplateErrorKind::UnknownEffect("Flying".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_typed_effect_is_a_parse_error() {
+        let source = r#"        Template(
+            name: "Potion",
+            entity_type: Item, glyph: '!', levels: [ 0 ], frequency: 1,
+            effects: Some([ Flying ]),
+        ),
+"#;
+        let errors = errors(source);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0].kind,
+            TemplateErrorKind::Parse(e) if e.starts_with("unknown variant `Flying`")
+        ));
+    }
+
+    #[test]
+    fn misspelt_ability_is_a_parse_error() {
+        let source = r#"        Template(
+            name: "Goblin",
+            entity_type: Enemy, glyph: 'g', levels: [ 0 ], frequency: 1, hp: Some(1),
+            abilities: Some([ Firebalt(cooldown: 3, range: 5, damage: 1) ]),
+        ),
+"#;
+        let errors = errors(source);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0].kind,
+            TemplateErrorKind::Parse(e) if e.starts_with("unknown variant `Firebalt`")
+        ));
+    }
+
+    #[test]
+    fn empty_levels() {
+        let source = r#"        Template(
+            name: "Goblin",
+            entity_type: Enemy, glyph: 'g', levels: [ ], frequency: 1, hp: Some(1),
+        ),
+"#;
+        assert_eq!(
+            errors(source),
+            vec![error("Goblin", 4, TemplateErrorKind::EmptyLevels)]
+        );
+    }
+
+    #[test]
+    fn zero_frequency() {
+        let source = r#"        Template(
+            name: "Goblin",
+            entity_type: Enemy, glyph: 'g', levels: [ 0 ], frequency: 0, hp: Some(1),
+        ),
+"#;
+        assert_eq!(
+            errors(source),
+            vec![error("Goblin", 4, TemplateErrorKind::ZeroFrequency)]
+        );
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let source = format!("{}{}", GOBLIN, GOBLIN);
+        assert_eq!(
+            errors(&source),
+            vec![error("Goblin", 8, TemplateErrorKind::DuplicateName)]
+        );
+    }
+
+    #[test]
+    fn glyph_outside_cp437() {
+        let source = r#"        Template(
+            name: "Snowman",
+            entity_type: Enemy, glyph: '☃', levels: [ 0 ], frequency: 1, hp: Some(1),
+        ),
+"#;
+        assert_eq!(
+            errors(source),
+            vec![error(
+                "Snowman",
+                4,
+                TemplateErrorKind::GlyphOutsideCp437('☃')
+            )]
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+#[system(for_each)]
+pub fn boss_phases(
+    entity: &Entity,
+    health: &Health,
+    phases: &mut BossPhases,
+    abilities: Option<&mut Abilities>,
+    commands: &mut CommandBuffer,
+) {
+    let health_percent = health.current * 100 / health.max;
+    let (triggered, remaining): (Vec<BossPhase>, Vec<BossPhase>) = phases
+        .0
+        .drain(..)
+        .partition(|phase| health_percent <= phase.health_percent);
+    phases.0 = remaining;
+
+    if triggered.is_empty() {
+        return;
+    }
+
+    let new_abilities = triggered.into_iter().flat_map(|phase| phase.abilities);
+    match abilities {
+        Some(abilities) => abilities.0.extend(new_abilities),
+        None => commands.add_component(*entity, Abilities(new_abilities.collect())),
+    }
+}
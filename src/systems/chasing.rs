@@ -1,4 +1,58 @@
 use crate::prelude::*;
+use std::collections::HashMap;
+
+const FOLLOW_DISTANCE: f32 = 2.0;
+
+pub struct Combatant {
+    pub entity: Entity,
+    pub pos: Point,
+    pub faction: String,
+    pub player: bool,
+}
+
+pub fn combatants(ecs: &SubWorld) -> Vec<Combatant> {
+    <(Entity, &Point, &Health, Option<&Faction>, Option<&Player>)>::query()
+        .iter(ecs)
+        .map(|(entity, pos, _, faction, player)| Combatant {
+            entity: *entity,
+            pos: *pos,
+            faction: faction.map_or(DEFAULT_FACTION.to_string(), |f| f.0.clone()),
+            player: player.is_some(),
+        })
+        .collect()
+}
+
+pub fn faction_of(combatants: &[Combatant], entity: Entity) -> &str {
+    combatants
+        .iter()
+        .find(|c| c.entity == entity)
+        .map_or(DEFAULT_FACTION, |c| c.faction.as_str())
+}
+
+pub fn nearest_hostile<'a>(
+    combatants: &'a [Combatant],
+    templates: &Templates,
+    light_map: &LightMap,
+    seeker: Entity,
+    fov: &FieldOfView,
+    alert: bool,
+) -> Option<&'a Combatant> {
+    let seeker = combatants.iter().find(|c| c.entity == seeker)?;
+    combatants
+        .iter()
+        .filter(|other| {
+            other.entity != seeker.entity
+                && templates.hostile(&seeker.faction, &other.faction)
+                && (alert || !other.player)
+                && fov.visible_tiles.contains(&other.pos)
+                && light_map.can_see(seeker.pos, other.pos)
+        })
+        .min_by(|a, b| {
+            let da = DistanceAlg::Pythagoras.distance2d(seeker.pos, a.pos);
+            let db = DistanceAlg::Pythagoras.distance2d(seeker.pos, b.pos);
+            da.partial_cmp(&db).unwrap()
+        })
+}
 
 #[system]
 #[read_component(Point)]
@@ -6,59 +60,167 @@ use crate::prelude::*;
 #[read_component(FieldOfView)]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn chasing(#[resource] map: &Map, ecs: &SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
-    let mut positions = <(Entity, &Point, &Health)>::query();
-    let mut player = <(&Point, &Player)>::query();
-    let player_pos = player.iter(ecs).next().unwrap().0;
-    let player_idx = map_idx(player_pos.x, player_pos.y);
-    let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &search_targets, map, 1024.0);
-    movers.iter(ecs).for_each(|(entity, pos, _, fov)| {
-        if !fov.visible_tiles.contains(player_pos) {
-            return;
-        }
-        let idx = map_idx(pos.x, pos.y);
-        if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map) {
-            let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
-            let destination = if distance > 1.2 {
-                map.index_to_point2d(destination)
-            } else {
-                *player_pos
+#[read_component(UsedAbility)]
+#[read_component(Pack)]
+#[read_component(Awareness)]
+#[read_component(Tunnelling)]
+#[read_component(Faction)]
+#[read_component(MovingRandomly)]
+pub fn chasing(
+    #[resource] map: &Map,
+    #[resource] light_map: &LightMap,
+    #[resource] templates: &Templates,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let mut movers = <(
+        Entity,
+        &Point,
+        Option<&ChasingPlayer>,
+        &FieldOfView,
+        Option<&Pack>,
+        Option<&Awareness>,
+        Option<&Tunnelling>,
+    )>::query()
+    .filter(
+        !component::<UsedAbility>()
+            & (component::<ChasingPlayer>() | component::<Pack>() | component::<MovingRandomly>()),
+    );
+    let targets = combatants(ecs);
+    let player_pos = *<&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let mut dijkstra_maps: HashMap<Point, DijkstraMap> = HashMap::new();
+
+    movers
+        .iter(ecs)
+        .for_each(|(entity, pos, chasing, fov, pack, awareness, tunnelling)| {
+            let faction = faction_of(&targets, *entity);
+            let pack_alerted = pack.map(|p| p.alerted).unwrap_or(false);
+            if !pack_alerted && awareness.is_some_and(|a| a.alertness == Alertness::Asleep) {
+                return;
+            }
+            let alert = awareness.is_none_or(|a| a.alertness == Alertness::Alert);
+            let hostile_to_player = templates.hostile(faction, PLAYER_FACTION);
+
+            let nearest = nearest_hostile(&targets, templates, light_map, *entity, fov, alert)
+                .map(|target| target.pos);
+            if chasing.is_none() && !pack_alerted && nearest.is_none() {
+                return;
+            }
+            let remembered = chasing.and_then(|c| {
+                c.last_seen
+                    .filter(|seen| c.lost_for < c.give_up_after && seen != pos)
+            });
+            if let Some(chasing) = chasing {
+                let updated = match nearest {
+                    Some(target) => ChasingPlayer {
+                        last_seen: Some(target),
+                        lost_for: 0,
+                        ..*chasing
+                    },
+                    None => ChasingPlayer {
+                        last_seen: remembered,
+                        lost_for: remembered.map_or(0, |_| chasing.lost_for + 1),
+                        ..*chasing
+                    },
+                };
+                if updated != *chasing {
+                    commands.add_component(*entity, updated);
+                }
+            }
+
+            let hunting = hostile_to_player && alert && tunnelling.is_some();
+            let pack_target = pack.filter(|p| p.alerted).and_then(|p| p.last_seen);
+            let (target, following) = match (nearest, remembered.or(pack_target)) {
+                (Some(target), _) => (target, false),
+                (None, _) if hunting => (player_pos, false),
+                (None, _) if !hostile_to_player => (player_pos, true),
+                (None, Some(last_seen)) => (last_seen, false),
+                (None, None) => return,
             };
 
-            let mut attacked = false;
-            positions
-                .iter(ecs)
-                .filter(|(_, target_pos, _)| **target_pos == destination)
-                .for_each(|(victim, _, _)| {
-                    attacked = true;
+            let distance = DistanceAlg::Pythagoras.distance2d(*pos, target);
+            if following && distance <= FOLLOW_DISTANCE {
+                return;
+            }
+            let dijkstra_map = dijkstra_maps.entry(target).or_insert_with(|| {
+                DijkstraMap::new(
+                    map.width,
+                    map.height,
+                    &[map.point2d_to_index(target)],
+                    map,
+                    1024.0,
+                )
+            });
+            let idx = map.point2d_to_index(*pos);
+
+            if tunnelling.is_some_and(|t| dijkstra_map.map[idx] > distance * t.detour)
+                && !following
+                && distance > 1.2
+            {
+                let delta = target - *pos;
+                let step = if delta.x.abs() >= delta.y.abs() {
+                    Point::new(delta.x.signum(), 0)
+                } else {
+                    Point::new(0, delta.y.signum())
+                };
+                let dig_target = *pos + step;
+                if map.can_dig(dig_target) {
+                    commands.push((
+                        (),
+                        WantsToDig {
+                            entity: *entity,
+                            target: dig_target,
+                        },
+                    ));
+                    return;
+                }
+                if map.can_enter_tile(dig_target)
+                    && !targets.iter().any(|other| other.pos == dig_target)
+                {
+                    commands.push((
+                        (),
+                        WantsToMove {
+                            entity: *entity,
+                            destination: dig_target,
+                        },
+                    ));
+                    return;
+                }
+            }
+
+            if let Some(destination) = DijkstraMap::find_lowest_exit(dijkstra_map, idx, map) {
+                let destination = if distance > 1.2 {
+                    map.index_to_point2d(destination)
+                } else {
+                    target
+                };
 
-                    if ecs
-                        .entry_ref(*victim)
-                        .unwrap()
-                        .get_component::<Player>()
-                        .is_ok()
-                    {
+                match targets.iter().find(|other| other.pos == destination) {
+                    Some(victim) => {
+                        if templates.hostile(faction, &victim.faction) {
+                            commands.push((
+                                (),
+                                WantsToAttack {
+                                    attacker: *entity,
+                                    victim: victim.entity,
+                                },
+                            ));
+                        }
+                    }
+                    None => {
                         commands.push((
                             (),
-                            WantsToAttack {
-                                attacker: *entity,
-                                victim: *victim,
+                            WantsToMove {
+                                entity: *entity,
+                                destination,
                             },
                         ));
                     }
-                });
-
-            if !attacked {
-                commands.push((
-                    (),
-                    WantsToMove {
-                        entity: *entity,
-                        destination,
-                    },
-                ));
+                }
             }
-        }
-    });
+        });
 }
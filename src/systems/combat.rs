@@ -1,12 +1,26 @@
 use crate::prelude::*;
 
+const COMBAT_NOISE: i32 = 6;
+
 #[system]
 #[read_component(WantsToAttack)]
 #[write_component(Player)]
 #[write_component(Health)]
 #[read_component(Damage)]
+#[read_component(DamageKind)]
+#[read_component(CombatStats)]
+#[read_component(Resistances)]
 #[read_component(Carried)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+#[read_component(Boss)]
+#[read_component(Point)]
+#[read_component(Name)]
+#[write_component(Awareness)]
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] config: &GameConfig,
+    #[resource] combat_log: &mut CombatLog,
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
 
     let victims: Vec<(Entity, Entity, Entity)> = attackers
@@ -14,54 +28,267 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
         .map(|(entity, attack)| (*entity, attack.attacker, attack.victim))
         .collect();
 
+    let mut rng = RandomNumberGenerator::new();
     victims.iter().for_each(|(message, attacker, victim)| {
-        let is_player = ecs
-            .entry_ref(*victim)
-            .unwrap()
-            .get_component::<Player>()
-            .is_ok();
-
-        let base_damage = if let Ok(v) = ecs.entry_ref(*attacker) {
-            if let Ok(dmg) = v.get_component::<Damage>() {
-                dmg.0
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-
-        let mut weapon = <(Entity, &Carried, &Damage)>::query();
+        if !is_alive(ecs, *attacker) || !is_alive(ecs, *victim) {
+            commands.remove(*message);
+            return;
+        }
+        let victim_is_player = is_player(ecs, *victim);
+        let attacker_is_player = is_player(ecs, *attacker);
 
-        let weapon_damage: i32 = weapon
-            .iter(ecs)
-            .filter(|(_, carried, _)| carried.0 == *attacker)
-            .map(|(_, _, dmg)| dmg.0)
-            .sum();
+        let sneak = attacker_is_player
+            && ecs.entry_ref(*victim).is_ok_and(|entry| {
+                entry
+                    .get_component::<Awareness>()
+                    .is_ok_and(|awareness| awareness.alertness != Alertness::Alert)
+            });
+        let attack = attack_of(ecs, *attacker, sneak);
+        let defence = defence_of(ecs, *victim);
+        let outcome = resolve_attack(&attack, &defence, &mut |sides| rng.roll_dice(1, sides));
+        combat_log.add(describe(
+            &name_of(ecs, *attacker),
+            &name_of(ecs, *victim),
+            attacker_is_player,
+            sneak,
+            outcome,
+        ));
 
-        let final_damage = base_damage + weapon_damage;
+        if let Ok(mut entry) = ecs.entry_mut(*victim) {
+            if let Ok(awareness) = entry.get_component_mut::<Awareness>() {
+                awareness.alertness = Alertness::Alert;
+            }
+            if let Ok(pos) = entry.get_component::<Point>() {
+                commands.push((
+                    (),
+                    Noise {
+                        pos: *pos,
+                        volume: COMBAT_NOISE,
+                    },
+                ));
+            }
+        }
 
-        weapon
+        <(Entity, &Carried, &Damage)>::query()
             .iter(ecs)
             .filter(|(_, carried, _)| carried.0 == *attacker)
             .for_each(|(entity, _, _)| {
                 commands.push(((), ReduceDurability { entity: *entity }));
             });
 
-        if let Ok(mut health) = ecs
+        let final_damage = match outcome {
+            AttackOutcome::Miss => 0,
+            AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) => damage,
+        };
+        if let Ok(health) = ecs
             .entry_mut(*victim)
             .unwrap()
             .get_component_mut::<Health>()
         {
             health.current -= final_damage;
-            if health.current < 1 && !is_player {
+            if health.current < 1 && !victim_is_player {
                 commands.remove(*victim);
+                drop_boss_loot(ecs, *victim, commands);
                 <(Entity, &mut Player)>::query()
                     .iter_mut(ecs)
                     .filter(|(entity, _)| *entity == attacker)
-                    .for_each(|(_, player)| player.score += 1000);
+                    .for_each(|(_, player)| player.score += config.score.kill_bonus);
             }
         }
         commands.remove(*message);
     });
 }
+
+fn is_alive(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity).is_ok_and(|entry| {
+        entry
+            .get_component::<Health>()
+            .is_ok_and(|health| health.current > 0)
+    })
+}
+
+fn is_player(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity)
+        .is_ok_and(|entry| entry.get_component::<Player>().is_ok())
+}
+
+fn name_of(ecs: &SubWorld, entity: Entity) -> String {
+    ecs.entry_ref(entity)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.0.clone())
+        })
+        .unwrap_or_else(|| "You".to_string())
+}
+
+fn attack_of(ecs: &SubWorld, attacker: Entity, sneak: bool) -> Attack {
+    let entry = ecs.entry_ref(attacker).ok();
+    let own_damage = entry
+        .as_ref()
+        .and_then(|entry| entry.get_component::<Damage>().ok().map(|damage| damage.0));
+    let own_kind = entry
+        .as_ref()
+        .and_then(|entry| entry.get_component::<DamageKind>().ok().map(|kind| kind.0));
+    let own_attack = entry
+        .as_ref()
+        .and_then(|entry| entry.get_component::<CombatStats>().ok())
+        .map_or(0, |stats| stats.attack);
+
+    let carried: Vec<(Option<&Damage>, Option<&DamageKind>, Option<&CombatStats>)> = <(
+        &Carried,
+        Option<&Damage>,
+        Option<&DamageKind>,
+        Option<&CombatStats>,
+    )>::query(
+    )
+    .iter(ecs)
+    .filter(|(carried, _, _, _)| carried.0 == attacker)
+    .map(|(_, damage, kind, stats)| (damage, kind, stats))
+    .collect();
+
+    Attack {
+        to_hit: own_attack
+            + carried
+                .iter()
+                .filter_map(|(_, _, stats)| stats.map(|stats| stats.attack))
+                .sum::<i32>(),
+        damage: own_damage
+            .into_iter()
+            .chain(
+                carried
+                    .iter()
+                    .filter_map(|(damage, _, _)| damage.map(|damage| damage.0)),
+            )
+            .collect(),
+        kind: carried
+            .iter()
+            .find_map(|(_, kind, _)| kind.map(|kind| kind.0))
+            .or(own_kind)
+            .unwrap_or(DamageType::Physical),
+        sneak,
+    }
+}
+
+fn defence_of(ecs: &SubWorld, victim: Entity) -> Defence {
+    let entry = ecs.entry_ref(victim).ok();
+    let own_defence = entry
+        .as_ref()
+        .and_then(|entry| entry.get_component::<CombatStats>().ok())
+        .map_or(0, |stats| stats.defence);
+    let carried_defence: i32 = <(&Carried, &CombatStats)>::query()
+        .iter(ecs)
+        .filter(|(carried, _)| carried.0 == victim)
+        .map(|(_, stats)| stats.defence)
+        .sum();
+
+    Defence {
+        defence: own_defence + carried_defence,
+        resistances: entry
+            .and_then(|entry| {
+                entry
+                    .get_component::<Resistances>()
+                    .ok()
+                    .map(|resistances| resistances.0.clone())
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn describe(
+    attacker: &str,
+    victim: &str,
+    attacker_is_player: bool,
+    sneak: bool,
+    outcome: AttackOutcome,
+) -> String {
+    let victim = if victim == "You" { "you" } else { victim };
+    let (hit, miss) = if attacker_is_player {
+        ("hit", "miss")
+    } else {
+        ("hits", "misses")
+    };
+    match outcome {
+        AttackOutcome::Miss => format!("{} {} {}.", attacker, miss, victim),
+        AttackOutcome::Hit(damage) if sneak => format!(
+            "{} catch {} unaware for {} damage!",
+            attacker, victim, damage
+        ),
+        AttackOutcome::Hit(damage) => {
+            format!("{} {} {} for {} damage.", attacker, hit, victim, damage)
+        }
+        AttackOutcome::Critical(damage) => format!(
+            "{} critically {} {} for {} damage!",
+            attacker, hit, victim, damage
+        ),
+    }
+}
+
+pub fn drop_boss_loot(ecs: &SubWorld, victim: Entity, commands: &mut CommandBuffer) {
+    if let Ok(entry) = ecs.entry_ref(victim) {
+        if let (Ok(_), Ok(pos)) = (
+            entry.get_component::<Boss>(),
+            entry.get_component::<Point>(),
+        ) {
+            drop_amulet_of_yala(commands, *pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_victims_are_not_killed_twice() {
+        let mut world = World::default();
+        let player = world.push((
+            Player {
+                map_level: 0,
+                score: 0,
+            },
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Damage(Dice::fixed(5)),
+        ));
+        let boss = world.push((
+            Boss,
+            Point::new(1, 1),
+            Health { current: 1, max: 1 },
+            Awareness {
+                alertness: Alertness::Unaware,
+                perception: 0,
+            },
+        ));
+        (0..2).for_each(|_| {
+            world.push((
+                (),
+                WantsToAttack {
+                    attacker: player,
+                    victim: boss,
+                },
+            ));
+        });
+
+        let mut resources = Resources::default();
+        resources.insert(GameConfig::load().unwrap());
+        resources.insert(CombatLog::default());
+        Schedule::builder()
+            .add_system(combat_system())
+            .build()
+            .execute(&mut world, &mut resources);
+
+        let amulets = <&AmuletOfYala>::query().iter(&world).count();
+        assert_eq!(amulets, 1);
+        let score = <&Player>::query().iter(&world).next().unwrap().score;
+        assert_eq!(
+            score,
+            resources.get::<GameConfig>().unwrap().score.kill_bonus
+        );
+        assert_eq!(<&WantsToAttack>::query().iter(&world).count(), 0);
+    }
+}
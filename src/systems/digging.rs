@@ -0,0 +1,47 @@
+use crate::prelude::*;
+
+const DIG_NOISE: i32 = 10;
+
+#[system(for_each)]
+#[read_component(Carried)]
+#[read_component(ProvidesDigging)]
+#[read_component(Tunnelling)]
+#[write_component(FieldOfView)]
+pub fn digging(
+    entity: &Entity,
+    want_dig: &WantsToDig,
+    #[resource] map: &mut Map,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let shovel = <(Entity, &Carried)>::query()
+        .filter(component::<ProvidesDigging>())
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == want_dig.entity)
+        .map(|(entity, _)| *entity)
+        .next();
+    let tunnels = ecs
+        .entry_ref(want_dig.entity)
+        .is_ok_and(|entry| entry.get_component::<Tunnelling>().is_ok());
+
+    if (shovel.is_some() || tunnels) && map.can_dig(want_dig.target) {
+        commands.push((
+            (),
+            Noise {
+                pos: want_dig.target,
+                volume: DIG_NOISE,
+            },
+        ));
+        if map.dig(want_dig.target) {
+            if let Some(shovel) = shovel {
+                commands.push(((), ReduceDurability { entity: shovel }));
+            }
+            <&mut FieldOfView>::query()
+                .iter_mut(ecs)
+                .filter(|fov| fov.visible_tiles.contains(&want_dig.target))
+                .for_each(|fov| fov.is_dirty = true);
+        }
+    }
+    // removes message entity
+    commands.remove(*entity);
+}
@@ -5,8 +5,14 @@ use crate::prelude::*;
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
-    let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
+#[read_component(Carried)]
+pub fn end_turn(
+    ecs: &SubWorld,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] map: &Map,
+    #[resource] config: &GameConfig,
+) {
+    let mut player_hp = <(Entity, &Health, &Point, &Player)>::query();
     let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
     let current_state = *turn_state;
     let mut new_state = match current_state {
@@ -19,16 +25,33 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
     let amulet_default = Point::new(-1, -1);
     let amulet_pos = amulet.iter(ecs).next().unwrap_or(&amulet_default);
 
-    player_hp.iter(ecs).for_each(|(hp, pos)| {
+    player_hp.iter(ecs).for_each(|(entity, hp, pos, player)| {
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if pos == amulet_pos {
+        if pos == amulet_pos && config.victory.contains(&VictoryCondition::Amulet) {
             new_state = TurnState::Victory;
         }
         let idx = map.point2d_to_index(*pos);
         if map.tiles[idx] == TileType::Exit {
-            new_state = TurnState::NextLevel;
+            let carrying_amulet = <&Carried>::query()
+                .filter(component::<AmuletOfYala>())
+                .iter(ecs)
+                .any(|carried| carried.0 == *entity);
+            if carrying_amulet && config.is_escape() {
+                new_state = if player.map_level == 0 {
+                    TurnState::Victory
+                } else {
+                    TurnState::NextLevel
+                };
+            } else if player.map_level as usize + 1 < config.levels {
+                new_state = TurnState::NextLevel;
+            }
+        }
+        if let Some(target) = config.score_target() {
+            if player.score >= target {
+                new_state = TurnState::Victory;
+            }
         }
     });
 
@@ -1,3 +1,4 @@
+use super::lighting::player_can_see;
 use crate::prelude::*;
 
 #[system]
@@ -5,21 +6,28 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
-    let mut renderables = <(&Point, &Render)>::query();
-    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).next().unwrap();
+#[read_component(Enemy)]
+#[read_component(LightSource)]
+pub fn entity_render(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] light_map: &LightMap,
+) {
+    let mut renderables = <(Entity, &Point, &Render)>::query();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    renderables
+    let mut visible: Vec<(&Point, &Render)> = renderables
         .iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
-        .for_each(|(pos, render)| {
-            draw_batch.set(*pos - offset, render.color, render.glyph);
-        });
+        .filter(|(entity, pos, _)| player_can_see(ecs, light_map, **entity, **pos))
+        .map(|(_, pos, render)| (pos, render))
+        .collect();
+    visible.sort_by_key(|(_, render)| render.order);
+    visible.into_iter().for_each(|(pos, render)| {
+        draw_batch.set(*pos - offset, render.color, render.glyph);
+    });
 
     draw_batch.submit(5000).expect("Batch error");
 }
@@ -1,3 +1,4 @@
+use super::lighting::player_can_see;
 use crate::prelude::*;
 
 #[system]
@@ -6,13 +7,29 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Name)]
-pub fn hud(ecs: &SubWorld, #[resource] timer: &mut Timer, #[resource] time_elapsed: &f32) {
+#[read_component(Boss)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[read_component(Enemy)]
+#[read_component(LightSource)]
+pub fn hud(
+    ecs: &SubWorld,
+    #[resource] timer: &mut Timer,
+    #[resource] time_elapsed: &f32,
+    #[resource] config: &GameConfig,
+    #[resource] template_status: &mut TemplateStatus,
+    #[resource] light_map: &LightMap,
+    #[resource] combat_log: &CombatLog,
+) {
     let mut health_query = <&Health>::query().filter(component::<Player>());
     let player_health = health_query.iter(ecs).next().unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move.");
+    draw_batch.print_centered(
+        1,
+        "Explore the Dungeon. Cursor keys to move, shift to run, ctrl to dig, click to inspect.",
+    );
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -31,12 +48,13 @@ pub fn hud(ecs: &SubWorld, #[resource] timer: &mut Timer, #[resource] time_elaps
 
     let (player, map_level, score) = <(Entity, &Player)>::query()
         .iter(ecs)
-        .find_map(|(entity, player)| Some((*entity, player.map_level, player.score)))
+        .map(|(entity, player)| (*entity, player.map_level, player.score))
+        .next()
         .unwrap();
 
     draw_batch.print_color_right(
         Point::new(SCREEN_WIDTH * 2, 1),
-        format!("Dungeon Level: {}", map_level + 1),
+        format!("Dungeon Level: {} / {}", map_level + 1, config.levels),
         ColorPair::new(YELLOW, BLACK),
     );
 
@@ -46,6 +64,12 @@ pub fn hud(ecs: &SubWorld, #[resource] timer: &mut Timer, #[resource] time_elaps
         ColorPair::new(YELLOW, BLACK),
     );
 
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 4),
+        format!("Difficulty: {:?}", config.difficulty),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
     timer.add(*time_elapsed);
     draw_batch.print_color_right(
         Point::new(SCREEN_WIDTH * 2, 3),
@@ -53,6 +77,26 @@ pub fn hud(ecs: &SubWorld, #[resource] timer: &mut Timer, #[resource] time_elaps
         ColorPair::new(YELLOW, BLACK),
     );
 
+    if template_status.reloaded_for > 0.0 {
+        template_status.reloaded_for -= *time_elapsed;
+        draw_batch.print_color_right(
+            Point::new(SCREEN_WIDTH * 2, 6),
+            "Templates reloaded",
+            ColorPair::new(GREEN, BLACK),
+        );
+    }
+    template_status
+        .errors
+        .iter()
+        .enumerate()
+        .for_each(|(i, error)| {
+            draw_batch.print_color_right(
+                Point::new(SCREEN_WIDTH * 2, 6 + i as i32),
+                error,
+                ColorPair::new(RED, BLACK),
+            );
+        });
+
     let mut item_query = <(&Item, &Name, &Carried)>::query();
     let mut y = 3;
     item_query
@@ -70,5 +114,38 @@ pub fn hud(ecs: &SubWorld, #[resource] timer: &mut Timer, #[resource] time_elaps
             ColorPair::new(YELLOW, BLACK),
         );
     }
+
+    let log_top = SCREEN_HEIGHT * 2 - 2 - combat_log.entries.len() as i32;
+    combat_log
+        .entries
+        .iter()
+        .enumerate()
+        .for_each(|(i, entry)| {
+            draw_batch.print_color_right(
+                Point::new(SCREEN_WIDTH * 2, log_top + i as i32),
+                entry,
+                ColorPair::new(WHITE, BLACK),
+            );
+        });
+
+    <(Entity, &Name, &Health, &Point)>::query()
+        .filter(component::<Boss>())
+        .iter(ecs)
+        .filter(|(entity, _, _, pos)| player_can_see(ecs, light_map, **entity, **pos))
+        .for_each(|(_, name, health, _)| {
+            let y = SCREEN_HEIGHT * 2 - 1;
+            draw_batch.bar_horizontal(
+                Point::new(0, y),
+                SCREEN_WIDTH * 2,
+                health.current,
+                health.max,
+                ColorPair::new(PURPLE, BLACK),
+            );
+            draw_batch.print_color_centered(
+                y,
+                format!(" {}: {} / {} ", &name.0, health.current, health.max),
+                ColorPair::new(WHITE, PURPLE),
+            );
+        });
     draw_batch.submit(10000).expect("Batch error");
 }
@@ -0,0 +1,132 @@
+use super::lighting::player_can_see;
+use crate::prelude::*;
+
+const PANEL_WIDTH: i32 = 40;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Description)]
+#[read_component(Health)]
+#[read_component(Damage)]
+#[read_component(CombatStats)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(LightSource)]
+#[read_component(Awareness)]
+#[read_component(Faction)]
+pub fn inspect(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] click: &MouseClick,
+    #[resource] camera: &Camera,
+    #[resource] inspect: &mut Inspect,
+    #[resource] light_map: &LightMap,
+) {
+    if click.0 {
+        let map_pos = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+        inspect.target = <(Entity, &Point, &Name)>::query()
+            .iter(ecs)
+            .filter(|(entity, pos, _)| {
+                **pos == map_pos && player_can_see(ecs, light_map, **entity, **pos)
+            })
+            .map(|(entity, _, _)| *entity)
+            .next();
+    }
+
+    let (target, entry) = match inspect
+        .target
+        .and_then(|target| ecs.entry_ref(target).ok().map(|entry| (target, entry)))
+    {
+        Some(target) => target,
+        None => {
+            inspect.target = None;
+            return;
+        }
+    };
+    match entry.get_component::<Point>() {
+        Ok(pos) if player_can_see(ecs, light_map, target, *pos) => {}
+        _ => {
+            inspect.target = None;
+            return;
+        }
+    }
+
+    let mut lines = Vec::new();
+    if let Ok(health) = entry.get_component::<Health>() {
+        lines.push(format!("Health: {} / {}", health.current, health.max));
+    }
+    if let Ok(damage) = entry.get_component::<Damage>() {
+        lines.push(format!("Damage: {}", damage.0));
+    }
+    if let Ok(stats) = entry.get_component::<CombatStats>() {
+        lines.push(format!(
+            "Attack: {:+}  Defence: {:+}",
+            stats.attack, stats.defence
+        ));
+    }
+    if let Ok(awareness) = entry.get_component::<Awareness>() {
+        match awareness.alertness {
+            Alertness::Asleep => lines.push("Asleep".to_string()),
+            Alertness::Unaware => lines.push("Unaware of you".to_string()),
+            Alertness::Alert => {}
+        }
+    }
+    if entry.get_component::<Player>().is_err()
+        && entry
+            .get_component::<Faction>()
+            .is_ok_and(|f| f.0 == PLAYER_FACTION)
+    {
+        lines.push("Allied with you".to_string());
+    }
+    if let Ok(description) = entry.get_component::<Description>() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(wrap(&description.0, PANEL_WIDTH as usize - 4));
+    }
+
+    let height = lines.len() as i32 + 3;
+    let top = SCREEN_HEIGHT * 2 - height - 3;
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.draw_double_box(
+        Rect::with_size(1, top, PANEL_WIDTH, height),
+        ColorPair::new(WHITE, BLACK),
+    );
+    if let Ok(name) = entry.get_component::<Name>() {
+        draw_batch.print_color(
+            Point::new(3, top + 1),
+            &name.0,
+            ColorPair::new(YELLOW, BLACK),
+        );
+    }
+    lines.iter().enumerate().for_each(|(i, line)| {
+        draw_batch.print(Point::new(3, top + 2 + i as i32), line);
+    });
+    draw_batch.print_color(
+        Point::new(3, top + height),
+        " Click elsewhere to close ",
+        ColorPair::new(GREY, BLACK),
+    );
+    draw_batch.submit(10200).expect("Batch error");
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    text.split_whitespace().for_each(|word| {
+        if !line.is_empty() && line.len() + word.len() + 1 > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    });
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
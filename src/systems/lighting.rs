@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Carried)]
+#[read_component(LightSource)]
+pub fn lighting(ecs: &SubWorld, #[resource] map: &Map, #[resource] light_map: &mut LightMap) {
+    light_map.reset();
+    <(Option<&Point>, Option<&Carried>, &LightSource)>::query()
+        .iter(ecs)
+        .filter_map(|(pos, carried, light)| {
+            let pos = match carried {
+                Some(carried) => ecs
+                    .entry_ref(carried.0)
+                    .ok()
+                    .and_then(|carrier| carrier.get_component::<Point>().ok().copied()),
+                None => pos.copied(),
+            };
+            pos.map(|pos| (pos, light))
+        })
+        .for_each(|(pos, light)| light_map.add_light(map, pos, light));
+}
+
+pub fn player_can_see(ecs: &SubWorld, light_map: &LightMap, entity: Entity, pos: Point) -> bool {
+    let (player_pos, player_fov) = <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    if !player_fov.visible_tiles.contains(&pos) {
+        return false;
+    }
+    ecs.entry_ref(entity).is_ok_and(|entry| {
+        entry.get_component::<Enemy>().is_err()
+            || entry.get_component::<LightSource>().is_ok()
+            || light_map.can_see(*player_pos, pos)
+    })
+}
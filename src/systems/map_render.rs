@@ -1,13 +1,13 @@
 use crate::prelude::*;
 
-#[allow(clippy::borrowed_box)]
 #[system]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn map_render(
     #[resource] map: &Map,
     #[resource] camera: &Camera,
-    #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] theme: &Theme,
+    #[resource] light_map: &LightMap,
     ecs: &SubWorld,
 ) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
@@ -21,17 +21,21 @@ pub fn map_render(
             let pt = Point::new(x, y);
             let offset = Point::new(camera.left_x, camera.top_y);
 
-            let idx = map_idx(x, y);
-            if map.in_bounds(pt) && player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx]
-            {
-                let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+            if !map.in_bounds(pt) {
+                continue;
+            }
+            let idx = map.idx(x, y);
+            if player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx] {
+                let (glyph, mut color) = theme.tile_to_render(map.tiles[idx], pt);
+                if map.tiles[idx] == TileType::Wall {
+                    color.fg = color.fg * RGBA::named(map.materials[idx].tint());
+                }
+                if player_fov.visible_tiles.contains(&pt) {
+                    color.fg = light_map.tint(pt, color.fg);
                 } else {
-                    DARK_GRAY
-                };
-
-                let glyph = theme.tile_to_render(map.tiles[idx]);
-                draw_batch.set(pt - offset, ColorPair::new(tint, BLACK), glyph);
+                    color = ColorPair::new(theme.remembered, BLACK);
+                }
+                draw_batch.set(pt - offset, color, glyph);
             }
         }
     }
@@ -1,28 +1,40 @@
 use crate::prelude::*;
 
+mod boss_phases;
 mod chasing;
 mod combat;
+mod digging;
 mod durability;
 mod end_turn;
 mod entity_render;
 mod fov;
 mod hud;
+mod inspect;
+mod lighting;
 mod map_render;
+mod monster_abilities;
 mod movement;
+mod noise;
+mod pack_alert;
+mod perception;
 mod player_input;
 mod random_move;
+mod reload_templates;
 mod tooltips;
 mod use_items;
 
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(reload_templates::reload_templates_system())
         .add_system(player_input::player_input_system())
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
         .add_system(tooltips::tooltips_system())
+        .add_system(inspect::inspect_system())
         .build()
 }
 
@@ -30,37 +42,51 @@ pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
+        .add_system(digging::digging_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(durability::durability_system())
         .flush()
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(inspect::inspect_system())
         .add_system(end_turn::end_turn_system())
         .build()
 }
 
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(boss_phases::boss_phases_system())
+        .add_system(noise::noise_system())
+        .flush()
+        .add_system(perception::perception_system())
+        .flush()
+        .add_system(monster_abilities::monster_abilities_system())
+        .add_system(pack_alert::pack_alert_system())
+        .flush()
         .add_system(random_move::random_move_system())
         .add_system(chasing::chasing_system())
         .flush()
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
+        .add_system(digging::digging_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(durability::durability_system())
         .flush()
         .add_system(fov::fov_system())
+        .add_system(lighting::lighting_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(inspect::inspect_system())
         .add_system(end_turn::end_turn_system())
         .build()
 }
@@ -0,0 +1,185 @@
+use super::chasing::{combatants, nearest_hostile};
+use super::combat::drop_boss_loot;
+use crate::prelude::*;
+use std::collections::HashMap;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(UsedAbility)]
+#[write_component(Abilities)]
+#[write_component(Health)]
+#[read_component(Boss)]
+#[read_component(Awareness)]
+#[read_component(Resistances)]
+#[read_component(Faction)]
+#[read_component(Summoned)]
+pub fn monster_abilities(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] templates: &Templates,
+    #[resource] light_map: &LightMap,
+) {
+    <Entity>::query()
+        .filter(component::<UsedAbility>())
+        .iter(ecs)
+        .for_each(|entity| commands.remove_component::<UsedAbility>(*entity));
+
+    let targets = combatants(ecs);
+
+    let mut summons = HashMap::<Entity, i32>::new();
+    <&Summoned>::query()
+        .iter(ecs)
+        .for_each(|summoned| *summons.entry(summoned.0).or_insert(0) += 1);
+
+    let mut casts = Vec::<(Entity, Point, Point, AbilityKind)>::new();
+    <(
+        Entity,
+        &Point,
+        &FieldOfView,
+        &Health,
+        &mut Abilities,
+        Option<&Awareness>,
+    )>::query()
+    .iter_mut(ecs)
+    .for_each(|(entity, pos, fov, health, abilities, awareness)| {
+        abilities
+            .0
+            .iter_mut()
+            .for_each(|a| a.ready_in = i32::max(0, a.ready_in - 1));
+
+        if awareness.is_some_and(|a| a.alertness == Alertness::Asleep) {
+            return;
+        }
+        let alert = awareness.is_none_or(|a| a.alertness == Alertness::Alert);
+        let target = match nearest_hostile(&targets, templates, light_map, *entity, fov, alert) {
+            Some(target) => target.pos,
+            None => return,
+        };
+        let distance = DistanceAlg::Pythagoras.distance2d(*pos, target);
+        if let Some(ability) = abilities.0.iter_mut().find(|a| {
+            a.ready_in == 0
+                && match a.kind {
+                    AbilityKind::Firebolt { .. } | AbilityKind::Breath { .. } => {
+                        distance > 1.5 && distance <= a.range as f32
+                    }
+                    AbilityKind::Heal { .. } => health.current < health.max,
+                    AbilityKind::Summon { limit, .. } => {
+                        summons.get(entity).copied().unwrap_or(0) < limit
+                    }
+                }
+        }) {
+            ability.ready_in = ability.cooldown;
+            casts.push((*entity, *pos, target, ability.kind.clone()));
+        }
+    });
+
+    let mut occupied: Vec<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    casts.iter().for_each(|(caster, pos, target, kind)| {
+        let mut used = true;
+        match kind {
+            AbilityKind::Firebolt { damage } => {
+                damage_at(ecs, &[*target], *damage, *caster, commands);
+            }
+            AbilityKind::Breath { damage } => {
+                let line: Vec<Point> = line2d(LineAlg::Bresenham, *pos, *target)
+                    .into_iter()
+                    .skip(1)
+                    .collect();
+                damage_at(ecs, &line, *damage, *caster, commands);
+            }
+            AbilityKind::Heal { amount } => {
+                if let Ok(health) = ecs
+                    .entry_mut(*caster)
+                    .unwrap()
+                    .get_component_mut::<Health>()
+                {
+                    health.current = i32::min(health.max, health.current + amount);
+                }
+            }
+            AbilityKind::Summon { template, .. } => {
+                let spot = [
+                    Point::new(-1, 0),
+                    Point::new(1, 0),
+                    Point::new(0, -1),
+                    Point::new(0, 1),
+                ]
+                .iter()
+                .map(|delta| *pos + *delta)
+                .find(|pt| map.can_enter_tile(*pt) && !occupied.contains(pt));
+
+                match (spot, templates.find(template)) {
+                    (Some(spot), Some(template)) => {
+                        let summoned = templates.spawn_entity(&spot, template, commands);
+                        commands.remove_component::<Awareness>(summoned);
+                        commands.add_component(summoned, Summoned(*caster));
+                        if let Ok(faction) =
+                            ecs.entry_ref(*caster).unwrap().get_component::<Faction>()
+                        {
+                            commands.add_component(summoned, faction.clone());
+                        }
+                        occupied.push(spot);
+                    }
+                    _ => used = false,
+                }
+            }
+        }
+
+        if used {
+            commands.add_component(*caster, UsedAbility);
+        } else if let Ok(abilities) = ecs
+            .entry_mut(*caster)
+            .unwrap()
+            .get_component_mut::<Abilities>()
+        {
+            abilities
+                .0
+                .iter_mut()
+                .filter(|a| a.kind == *kind)
+                .for_each(|a| a.ready_in = 0);
+        }
+    });
+}
+
+fn damage_at(
+    ecs: &mut SubWorld,
+    targets: &[Point],
+    damage: i32,
+    caster: Entity,
+    commands: &mut CommandBuffer,
+) {
+    let mut victims = <(
+        Entity,
+        &Point,
+        &mut Health,
+        Option<&Player>,
+        Option<&Resistances>,
+    )>::query();
+    let mut slain = Vec::new();
+    victims
+        .iter_mut(ecs)
+        .filter(|(entity, pos, health, _, _)| {
+            **entity != caster && health.current > 0 && targets.contains(pos)
+        })
+        .for_each(|(entity, _, health, player, resistances)| {
+            let resistance = resistances
+                .and_then(|r| r.0.get(&DamageType::Fire).copied())
+                .unwrap_or(0);
+            health.current -= resist(damage, resistance);
+            if health.current < 1 && player.is_none() {
+                slain.push(*entity);
+            }
+        });
+
+    slain.iter().for_each(|entity| {
+        commands.remove(*entity);
+        drop_boss_loot(ecs, *entity, commands);
+    });
+}
@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(Noise)]
+#[read_component(Point)]
+#[write_component(Awareness)]
+pub fn noise(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let noises: Vec<(Entity, Noise)> = <(Entity, &Noise)>::query()
+        .iter(ecs)
+        .map(|(entity, noise)| (*entity, *noise))
+        .collect();
+
+    noises.iter().for_each(|(message, noise)| {
+        let start = map.point2d_to_index(noise.pos);
+        let mut dijkstra_map = DijkstraMap::new(map.width, map.height, &[start], map, 1024.0);
+        dijkstra_map.map[start] = 0.0;
+
+        <(&Point, &mut Awareness)>::query()
+            .iter_mut(ecs)
+            .filter(|(pos, awareness)| {
+                let distance = dijkstra_map.map[map.point2d_to_index(**pos)];
+                distance <= (noise.volume + awareness.perception) as f32
+            })
+            .for_each(|(_, awareness)| {
+                awareness.alertness = match awareness.alertness {
+                    Alertness::Asleep => Alertness::Unaware,
+                    _ => Alertness::Alert,
+                }
+            });
+        commands.remove(*message);
+    });
+}
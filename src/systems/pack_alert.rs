@@ -0,0 +1,53 @@
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+const PACK_MEMORY: i32 = 8;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[write_component(Pack)]
+#[write_component(Awareness)]
+pub fn pack_alert(ecs: &mut SubWorld, #[resource] light_map: &LightMap) {
+    let player_pos = *<&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    let sighted: HashSet<Entity> = <(&Pack, &Point, &FieldOfView, Option<&Awareness>)>::query()
+        .iter(ecs)
+        .filter(|(_, pos, fov, awareness)| {
+            awareness.is_none_or(|a| a.alertness == Alertness::Alert)
+                && fov.visible_tiles.contains(&player_pos)
+                && light_map.can_see(**pos, player_pos)
+        })
+        .map(|(pack, _, _, _)| pack.id)
+        .collect();
+
+    let mut remembered = HashMap::<Entity, (i32, Option<Point>)>::new();
+    <&Pack>::query().iter(ecs).for_each(|pack| {
+        let entry = remembered.entry(pack.id).or_insert((0, None));
+        if pack.memory > entry.0 {
+            *entry = (pack.memory, pack.last_seen);
+        }
+    });
+
+    <(&mut Pack, Option<&mut Awareness>)>::query()
+        .iter_mut(ecs)
+        .for_each(|(pack, awareness)| {
+            if sighted.contains(&pack.id) {
+                pack.memory = PACK_MEMORY;
+                pack.last_seen = Some(player_pos);
+            } else {
+                let (memory, last_seen) = remembered[&pack.id];
+                pack.memory = i32::max(0, memory - 1);
+                pack.last_seen = last_seen.filter(|_| pack.memory > 0);
+            }
+            pack.alerted = pack.memory > 0;
+            if let (true, Some(awareness)) = (pack.alerted, awareness) {
+                awareness.alertness = Alertness::Alert;
+            }
+        });
+}
@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+const STEALTH: i32 = 10;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[write_component(Awareness)]
+pub fn perception(ecs: &mut SubWorld, #[resource] light_map: &LightMap) {
+    let player_pos = *<&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    let mut rng = RandomNumberGenerator::new();
+    <(&Point, &FieldOfView, &mut Awareness)>::query()
+        .iter_mut(ecs)
+        .filter(|(pos, fov, awareness)| {
+            awareness.alertness == Alertness::Unaware
+                && fov.visible_tiles.contains(&player_pos)
+                && light_map.can_see(**pos, player_pos)
+        })
+        .for_each(|(pos, _, awareness)| {
+            let distance = DistanceAlg::Pythagoras.distance2d(*pos, player_pos) as i32;
+            if rng.range(1, 21) + awareness.perception >= STEALTH + distance {
+                awareness.alertness = Alertness::Alert;
+            }
+        });
+}
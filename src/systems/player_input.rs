@@ -1,5 +1,7 @@
 use crate::prelude::*;
 
+const RUN_NOISE: i32 = 8;
+
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
@@ -8,11 +10,14 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Weapon)]
+#[read_component(Faction)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
     #[resource] turn_state: &mut TurnState,
+    #[resource] modifiers: &KeyModifiers,
+    #[resource] map: &Map,
 ) {
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
 
@@ -25,7 +30,8 @@ pub fn player_input(
             VirtualKeyCode::G => {
                 let (player, player_pos) = players
                     .iter(ecs)
-                    .find_map(|(entity, pos)| Some((*entity, *pos)))
+                    .map(|(entity, pos)| (*entity, *pos))
+                    .next()
                     .unwrap();
                 let mut items = <(Entity, &Item, &Point)>::query();
                 items
@@ -60,14 +66,44 @@ pub fn player_input(
             _ => Point::zero(),
         };
 
-        let (player_entity, destination) = players
+        let (player_entity, player_pos) = players
             .iter(ecs)
-            .find_map(|(entity, pos)| Some((*entity, *pos + delta)))
+            .map(|(entity, pos)| (*entity, *pos))
+            .next()
             .unwrap();
+        let mut destination = player_pos + delta;
 
         let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
 
-        if delta.x != 0 || delta.y != 0 {
+        let sprint = destination + delta;
+        if modifiers.shift
+            && !modifiers.control
+            && (delta.x != 0 || delta.y != 0)
+            && map.can_enter_tile(destination)
+            && map.can_enter_tile(sprint)
+            && !enemies
+                .iter(ecs)
+                .any(|(_, pos)| *pos == destination || *pos == sprint)
+        {
+            destination = sprint;
+            commands.push((
+                (),
+                Noise {
+                    pos: destination,
+                    volume: RUN_NOISE,
+                },
+            ));
+        }
+
+        if modifiers.control && (delta.x != 0 || delta.y != 0) {
+            commands.push((
+                (),
+                WantsToDig {
+                    entity: player_entity,
+                    target: destination,
+                },
+            ));
+        } else if delta.x != 0 || delta.y != 0 {
             let mut hit_something = false;
             enemies
                 .iter(ecs)
@@ -75,6 +111,28 @@ pub fn player_input(
                 .for_each(|(entity, _)| {
                     hit_something = true;
 
+                    let ally = ecs
+                        .entry_ref(*entity)
+                        .unwrap()
+                        .get_component::<Faction>()
+                        .is_ok_and(|f| f.0 == PLAYER_FACTION);
+                    if ally {
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: *entity,
+                                destination: player_pos,
+                            },
+                        ));
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: player_entity,
+                                destination,
+                            },
+                        ));
+                        return;
+                    }
                     commands.push((
                         (),
                         WantsToAttack {
@@ -101,7 +159,8 @@ pub fn player_input(
 fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
     let player_entity = <(Entity, &Player)>::query()
         .iter(ecs)
-        .find_map(|(entity, _player)| Some(*entity))
+        .map(|(entity, _player)| *entity)
+        .next()
         .unwrap();
 
     let item_entity = <(Entity, &Item, &Carried)>::query()
@@ -109,7 +168,8 @@ fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point
         .filter(|(_, _, carried)| carried.0 == player_entity)
         .enumerate()
         .filter(|(item_count, (_, _, _))| *item_count == n)
-        .find_map(|(_, (entity, _, _))| Some(*entity));
+        .map(|(_, (entity, _, _))| *entity)
+        .next();
 
     if let Some(item_entity) = item_entity {
         commands.push((
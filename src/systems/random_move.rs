@@ -1,3 +1,4 @@
+use super::chasing::{combatants, faction_of, nearest_hostile};
 use crate::prelude::*;
 
 #[system]
@@ -5,50 +6,73 @@ use crate::prelude::*;
 #[read_component(MovingRandomly)]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &MovingRandomly)>::query();
-    let mut positions = <(Entity, &Point)>::query().filter(component::<Health>());
+#[read_component(UsedAbility)]
+#[read_component(Pack)]
+#[read_component(Awareness)]
+#[read_component(Faction)]
+#[read_component(FieldOfView)]
+pub fn random_move(
+    #[resource] templates: &Templates,
+    #[resource] light_map: &LightMap,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let mut movers = <(
+        Entity,
+        &Point,
+        &MovingRandomly,
+        &FieldOfView,
+        Option<&Pack>,
+        Option<&Awareness>,
+    )>::query()
+    .filter(!component::<UsedAbility>());
+    let targets = combatants(ecs);
 
-    movers.iter(ecs).for_each(|(entity, pos, _)| {
-        let mut rng = RandomNumberGenerator::new();
-        let destination = match rng.range(0, 4) {
-            0 => Point::new(-1, 0),
-            1 => Point::new(1, 0),
-            2 => Point::new(0, -1),
-            _ => Point::new(0, 1),
-        } + *pos;
+    movers
+        .iter(ecs)
+        .for_each(|(entity, pos, moving, fov, pack, awareness)| {
+            if pack.map(|p| p.alerted).unwrap_or(false)
+                || awareness.is_some_and(|a| a.alertness == Alertness::Asleep)
+            {
+                return;
+            }
+            let alert = awareness.is_none_or(|a| a.alertness == Alertness::Alert);
+            if nearest_hostile(&targets, templates, light_map, *entity, fov, alert).is_some() {
+                return;
+            }
+            let mut rng = RandomNumberGenerator::new();
+            if rng.rand::<f32>() >= moving.wander_chance {
+                return;
+            }
+            let destination = match rng.range(0, 4) {
+                0 => Point::new(-1, 0),
+                1 => Point::new(1, 0),
+                2 => Point::new(0, -1),
+                _ => Point::new(0, 1),
+            } + *pos;
 
-        let mut attacked = false;
-        positions
-            .iter(ecs)
-            .filter(|(_, target_pos)| **target_pos == destination)
-            .for_each(|(victim, _)| {
-                attacked = true;
-
-                if ecs
-                    .entry_ref(*victim)
-                    .unwrap()
-                    .get_component::<Player>()
-                    .is_ok()
-                {
+            let faction = faction_of(&targets, *entity);
+            match targets.iter().find(|other| other.pos == destination) {
+                Some(victim) => {
+                    if templates.hostile(faction, &victim.faction) {
+                        commands.push((
+                            (),
+                            WantsToAttack {
+                                attacker: *entity,
+                                victim: victim.entity,
+                            },
+                        ));
+                    }
+                }
+                None => {
                     commands.push((
                         (),
-                        WantsToAttack {
-                            attacker: *entity,
-                            victim: *victim,
+                        WantsToMove {
+                            entity: *entity,
+                            destination,
                         },
                     ));
                 }
-            });
-
-        if !attacked {
-            commands.push((
-                (),
-                WantsToMove {
-                    entity: *entity,
-                    destination,
-                },
-            ));
-        }
-    });
+            }
+        });
 }
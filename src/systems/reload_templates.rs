@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+#[system]
+pub fn reload_templates(
+    #[resource] key: &mut Option<VirtualKeyCode>,
+    #[resource] config: &GameConfig,
+    #[resource] templates: &mut Templates,
+    #[resource] status: &mut TemplateStatus,
+) {
+    if !cfg!(debug_assertions) || *key != Some(VirtualKeyCode::F5) {
+        return;
+    }
+    *key = None;
+
+    match Templates::load(config) {
+        Ok(reloaded) => {
+            *templates = reloaded;
+            status.errors.clear();
+            status.reloaded_for = 2000.0;
+        }
+        Err(errors) => {
+            status.errors = vec!["Reload failed, keeping previous templates:".to_string()];
+            status.errors.extend(errors.iter().map(|e| e.to_string()));
+            status.reloaded_for = 0.0;
+        }
+    }
+}
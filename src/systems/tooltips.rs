@@ -1,3 +1,4 @@
+use super::lighting::player_can_see;
 use crate::prelude::*;
 
 #[system]
@@ -6,19 +7,25 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera: &Camera) {
+#[read_component(Enemy)]
+#[read_component(LightSource)]
+pub fn tooltips(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] light_map: &LightMap,
+) {
     let mut positions = <(Entity, &Point, &Name)>::query();
     let offset = Point::new(camera.left_x, camera.top_y);
     let map_pos = *mouse_pos + offset;
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
 
-    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).next().unwrap();
-
     positions
         .iter(ecs)
-        .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(pos))
+        .filter(|(entity, pos, _)| {
+            **pos == map_pos && player_can_see(ecs, light_map, **entity, **pos)
+        })
         .for_each(|(entity, _, name)| {
             let screen_pos = *mouse_pos * 4;
             let display =
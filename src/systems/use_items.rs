@@ -1,21 +1,37 @@
 use crate::prelude::*;
 
+const ALLY_GIVE_UP_AFTER: i32 = 3;
+
 #[system]
 #[read_component(ActivateItem)]
 #[read_component(ProvidesHealing)]
 #[write_component(Health)]
 #[read_component(ProvidesDungeonMap)]
 #[read_component(ProvidesVision)]
+#[read_component(LightSource)]
 #[write_component(FieldOfView)]
-pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+#[read_component(ProvidesCharm)]
+#[read_component(Point)]
+#[read_component(Enemy)]
+#[read_component(Boss)]
+#[read_component(Faction)]
+pub fn use_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] combat_log: &mut CombatLog,
+) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
     let mut vision_to_apply = Vec::<(Entity, i32)>::new();
+    let mut charm_to_apply = Vec::<Entity>::new();
 
     <(Entity, &ActivateItem)>::query()
         .iter(ecs)
         .for_each(|(entity, activate)| {
             let item = ecs.entry_ref(activate.item);
+            let mut consumed = true;
             if let Ok(item) = item {
+                consumed = item.get_component::<LightSource>().is_err();
                 if let Ok(healing) = item.get_component::<ProvidesHealing>() {
                     healing_to_apply.push((activate.used_by, healing.amount));
                 }
@@ -27,9 +43,21 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
                 if let Ok(vision) = item.get_component::<ProvidesVision>() {
                     vision_to_apply.push((activate.used_by, vision.amount));
                 }
+
+                if let Ok(_charm) = item.get_component::<ProvidesCharm>() {
+                    match charm_target(ecs, activate.used_by) {
+                        Some(target) => charm_to_apply.push(target),
+                        None => {
+                            consumed = false;
+                            combat_log.add("The scroll finds no one to charm.".to_string());
+                        }
+                    }
+                }
             }
 
-            commands.remove(activate.item);
+            if consumed {
+                commands.remove(activate.item);
+            }
             commands.remove(*entity)
         });
 
@@ -49,4 +77,32 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
             }
         }
     }
+
+    for target in charm_to_apply.iter() {
+        commands.add_component(*target, Faction(PLAYER_FACTION.to_string()));
+        commands.add_component(*target, ChasingPlayer::new(ALLY_GIVE_UP_AFTER));
+        commands.remove_component::<Awareness>(*target);
+        commands.remove_component::<Pack>(*target);
+        commands.remove_component::<MovingRandomly>(*target);
+    }
+}
+
+fn charm_target(ecs: &SubWorld, charmer: Entity) -> Option<Entity> {
+    let charmer = ecs.entry_ref(charmer).ok()?;
+    let pos = *charmer.get_component::<Point>().ok()?;
+    let fov = charmer.get_component::<FieldOfView>().ok()?;
+    let faction = charmer.get_component::<Faction>().ok()?;
+
+    <(Entity, &Point, Option<&Faction>)>::query()
+        .filter(component::<Enemy>() & !component::<Boss>())
+        .iter(ecs)
+        .filter(|(_, target_pos, target_faction)| {
+            fov.visible_tiles.contains(target_pos) && target_faction.is_none_or(|f| f != faction)
+        })
+        .min_by(|(_, a, _), (_, b, _)| {
+            let da = DistanceAlg::Pythagoras.distance2d(pos, **a);
+            let db = DistanceAlg::Pythagoras.distance2d(pos, **b);
+            da.partial_cmp(&db).unwrap()
+        })
+        .map(|(entity, _, _)| *entity)
 }